@@ -0,0 +1,13 @@
+#![no_main]
+use aoc_template::days::Day;
+use aoc_template::with_day;
+use libfuzzer_sys::fuzz_target;
+
+// The first byte picks the day, and the rest is its input
+fuzz_target!(|data: &[u8]| {
+  if let Some((&day, input)) = data.split_first() {
+    if let (1..=25, Ok(input)) = (day, std::str::from_utf8(input)) {
+      let _ = with_day!(day, D => D::parse(input).is_ok());
+    }
+  }
+});
@@ -0,0 +1,168 @@
+use std::cmp::Ordering;
+use std::fmt::{self, Display, Formatter};
+use std::ops::{Add, Mul};
+
+// A non-negative integer of any size, for answers that do not fit in a u64. Stored as 32-bit
+// limbs with the least significant first, without zero limbs at the end, so equal numbers have
+// equal limbs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigUint {
+  limbs: Vec<u32>,
+}
+
+impl BigUint {
+  pub fn zero() -> BigUint {
+    BigUint::default()
+  }
+
+  pub fn is_zero(&self) -> bool {
+    self.limbs.is_empty()
+  }
+
+  fn normalised(mut limbs: Vec<u32>) -> BigUint {
+    while limbs.last() == Some(&0) {
+      limbs.pop();
+    }
+    BigUint { limbs }
+  }
+
+  // The number with these hexadecimal digits, the most significant first
+  pub fn from_nibbles(nibbles: &[u8]) -> BigUint {
+    let mut limbs = vec![0; nibbles.len().div_ceil(8)];
+    for (i, nibble) in nibbles.iter().rev().enumerate() {
+      limbs[i / 8] |= (*nibble as u32 & 0xF) << (i % 8 * 4);
+    }
+    BigUint::normalised(limbs)
+  }
+
+  // The hexadecimal digits of the number, the most significant first. Zero has a single digit.
+  pub fn nibbles(&self) -> Vec<u8> {
+    let mut res: Vec<u8> = self
+      .limbs
+      .iter()
+      .flat_map(|limb| (0..8).map(move |i| (limb >> (i * 4) & 0xF) as u8))
+      .collect();
+    while res.len() > 1 && res.last() == Some(&0) {
+      res.pop();
+    }
+    if res.is_empty() {
+      res.push(0);
+    }
+    res.reverse();
+    res
+  }
+
+  pub fn to_u64(&self) -> Option<u64> {
+    match self.limbs[..] {
+      [] => Some(0),
+      [low] => Some(low as u64),
+      [low, high] => Some((high as u64) << 32 | low as u64),
+      _ => None,
+    }
+  }
+
+  // Divides in place by a small divisor, and returns the remainder
+  fn div_rem_small(&mut self, divisor: u32) -> u32 {
+    let mut rem = 0u64;
+    for limb in self.limbs.iter_mut().rev() {
+      let acc = rem << 32 | *limb as u64;
+      *limb = (acc / divisor as u64) as u32;
+      rem = acc % divisor as u64;
+    }
+    *self = BigUint::normalised(std::mem::take(&mut self.limbs));
+    rem as u32
+  }
+}
+
+impl From<u64> for BigUint {
+  fn from(n: u64) -> BigUint {
+    BigUint::normalised(vec![n as u32, (n >> 32) as u32])
+  }
+}
+
+impl Add for &BigUint {
+  type Output = BigUint;
+
+  fn add(self, other: &BigUint) -> BigUint {
+    let mut limbs = Vec::with_capacity(self.limbs.len().max(other.limbs.len()) + 1);
+    let mut carry = 0u64;
+    for i in 0..self.limbs.len().max(other.limbs.len()) {
+      let a = *self.limbs.get(i).unwrap_or(&0) as u64;
+      let b = *other.limbs.get(i).unwrap_or(&0) as u64;
+      let sum = a + b + carry;
+      limbs.push(sum as u32);
+      carry = sum >> 32;
+    }
+    limbs.push(carry as u32);
+    BigUint::normalised(limbs)
+  }
+}
+
+impl Add for BigUint {
+  type Output = BigUint;
+
+  fn add(self, other: BigUint) -> BigUint {
+    &self + &other
+  }
+}
+
+impl Mul for &BigUint {
+  type Output = BigUint;
+
+  fn mul(self, other: &BigUint) -> BigUint {
+    let mut limbs = vec![0u32; self.limbs.len() + other.limbs.len()];
+    for (i, a) in self.limbs.iter().enumerate() {
+      let mut carry = 0u64;
+      for (j, b) in other.limbs.iter().enumerate() {
+        let acc = limbs[i + j] as u64 + *a as u64 * *b as u64 + carry;
+        limbs[i + j] = acc as u32;
+        carry = acc >> 32;
+      }
+      limbs[i + other.limbs.len()] = carry as u32;
+    }
+    BigUint::normalised(limbs)
+  }
+}
+
+impl Mul for BigUint {
+  type Output = BigUint;
+
+  fn mul(self, other: BigUint) -> BigUint {
+    &self * &other
+  }
+}
+
+impl Ord for BigUint {
+  fn cmp(&self, other: &BigUint) -> Ordering {
+    self
+      .limbs
+      .len()
+      .cmp(&other.limbs.len())
+      .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+  }
+}
+
+impl PartialOrd for BigUint {
+  fn partial_cmp(&self, other: &BigUint) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Display for BigUint {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    if let Some(n) = self.to_u64() {
+      return write!(f, "{}", n);
+    }
+    // Nine decimal digits at a time, the least significant first
+    let mut rest = self.clone();
+    let mut chunks = vec![];
+    while !rest.is_zero() {
+      chunks.push(rest.div_rem_small(1_000_000_000));
+    }
+    let mut res = chunks.pop().unwrap().to_string();
+    for chunk in chunks.iter().rev() {
+      res.push_str(&format!("{:09}", chunk));
+    }
+    write!(f, "{}", res)
+  }
+}
@@ -1,8 +1,8 @@
-use nom::character::complete::{i32, newline};
-use nom::multi::separated_list0;
+use crate::parser::{int, lines, print_list};
 use nom::IResult;
+use rand::Rng;
 
-use crate::days::Day;
+use crate::days::{Day, Explore, Generate, NoSolution, Print};
 
 pub struct Day01;
 
@@ -20,23 +20,44 @@ impl Day for Day01 {
   type Input = Vec<i32>;
 
   fn parse(input: &str) -> IResult<&str, Self::Input> {
-    separated_list0(newline, i32)(input)
+    lines(int)(input)
   }
 
   type Output1 = i32;
 
-  fn part_1(input: &Self::Input) -> Self::Output1 {
-    helper(input)
+  fn part_1(input: &Self::Input) -> Result<Self::Output1, NoSolution> {
+    Ok(helper(input))
   }
 
 
   type Output2 = i32;
 
-  fn part_2(input: &Self::Input) -> Self::Output2 {
+  fn part_2(input: &Self::Input) -> Result<Self::Output2, NoSolution> {
     let mut tmp = vec![];
     for i in 1..input.len()-1 {
       tmp.push(input[i-1]+input[i]+input[i+1])
     }
-    helper(&tmp)
+    Ok(helper(&tmp))
   }
 }
+
+impl Generate for Day01 {
+  fn generate<R: Rng>(rng: &mut R, scale: usize) -> String {
+    let mut depth: i32 = rng.gen_range(100..200);
+    (0..2000 * scale)
+      .map(|_| {
+        depth = i32::max(0, depth + rng.gen_range(-10..=30));
+        depth.to_string()
+      })
+      .collect::<Vec<String>>()
+      .join("\n")
+  }
+}
+
+impl Print for Day01 {
+  fn print(input: &Self::Input) -> String {
+    print_list(input, "\n")
+  }
+}
+
+impl Explore for Day01 {}
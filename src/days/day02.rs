@@ -1,22 +1,34 @@
+use crate::parser::{lines, print_list};
 use nom::branch::alt;
 use nom::bytes::complete::tag;
-use nom::character::complete::{i32, newline};
+use nom::character::complete::i32;
 use nom::combinator::map as pmap;
-use nom::multi::separated_list0;
 use nom::sequence::pair;
 use nom::IResult;
+use rand::Rng;
+use std::fmt::{self, Display, Formatter};
 
-use crate::days::Day;
+use crate::days::{Day, Explore, Generate, NoSolution, Print};
 
 pub struct Day02;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum Move {
   Forward(i32),
   Down(i32),
   Up(i32),
 }
 
+impl Display for Move {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match self {
+      Move::Forward(x) => write!(f, "forward {}", x),
+      Move::Down(x) => write!(f, "down {}", x),
+      Move::Up(x) => write!(f, "up {}", x),
+    }
+  }
+}
+
 fn parse_move(input: &str) -> IResult<&str, Move> {
   alt((
     pmap(pair(tag("forward "), i32), |(_, x)| Move::Forward(x)),
@@ -29,12 +41,12 @@ impl Day for Day02 {
   type Input = Vec<Move>;
 
   fn parse(input: &str) -> IResult<&str, Self::Input> {
-    separated_list0(newline, parse_move)(input)
+    lines(parse_move)(input)
   }
 
   type Output1 = i32;
 
-  fn part_1(input: &Self::Input) -> Self::Output1 {
+  fn part_1(input: &Self::Input) -> Result<Self::Output1, NoSolution> {
     let mut x = 0;
     let mut y = 0;
     for m in input {
@@ -44,12 +56,12 @@ impl Day for Day02 {
         Move::Down(d) => y += d,
       }
     }
-    x * y
+    Ok(x * y)
   }
 
   type Output2 = i32;
 
-  fn part_2(input: &Self::Input) -> Self::Output2 {
+  fn part_2(input: &Self::Input) -> Result<Self::Output2, NoSolution> {
     let mut aim = 0;
     let mut x = 0;
     let mut y = 0;
@@ -63,6 +75,26 @@ impl Day for Day02 {
         Move::Down(d) => aim += d,
       }
     }
-    x * y
+    Ok(x * y)
   }
 }
+
+impl Generate for Day02 {
+  fn generate<R: Rng>(rng: &mut R, scale: usize) -> String {
+    (0..1000 * scale)
+      .map(|_| {
+        let direction = ["forward", "down", "up"][rng.gen_range(0..3)];
+        format!("{} {}", direction, rng.gen_range(1..=9))
+      })
+      .collect::<Vec<String>>()
+      .join("\n")
+  }
+}
+
+impl Print for Day02 {
+  fn print(input: &Self::Input) -> String {
+    print_list(input, "\n")
+  }
+}
+
+impl Explore for Day02 {}
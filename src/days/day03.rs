@@ -1,10 +1,9 @@
-use crate::parser::grid;
-use nom::branch::alt;
-use nom::character::complete::char;
-use nom::combinator::map as pmap;
+use crate::parser::{char_map, grid, Grid};
 use nom::IResult;
+use rand::seq::index::sample;
+use rand::Rng;
 
-use crate::days::Day;
+use crate::days::{Day, Explore, Generate, NoSolution, Print};
 
 pub struct Day03;
 
@@ -13,18 +12,16 @@ enum FilterMode {
   LeastCommon,
 }
 
-fn transpose<T: Copy>(input: &[Vec<T>]) -> Vec<Vec<T>> {
-  (0..input[0].len())
-    .map(|i| input.iter().map(|r| r[i]).collect())
-    .collect()
-}
-
 fn filter_common(input: &[Vec<u32>], i: usize, mode: FilterMode) -> Vec<Vec<u32>> {
   if input.len() <= 1 {
     return input.to_owned();
   }
   let input_len = input.len() as u32;
   let count = input.iter().map(|r| r[i]).sum::<u32>();
+  // If all numbers have the same bit, there is no least common one to keep
+  if count == 0 || count == input_len {
+    return input.to_owned();
+  }
   let filter_key = match (count * 2 >= input_len, mode) {
     (true, FilterMode::MostCommon) | (false, FilterMode::LeastCommon) => 1,
     _ => 0,
@@ -42,21 +39,20 @@ fn parse_bits(input: &[u32]) -> u32 {
 }
 
 impl Day for Day03 {
-  type Input = Vec<Vec<u32>>;
+  type Input = Grid<u32>;
 
   fn parse(input: &str) -> IResult<&str, Self::Input> {
-    grid(alt((pmap(char('0'), |_| 0), pmap(char('1'), |_| 1))))(input)
+    grid(char_map(&[('0', 0), ('1', 1)]))(input)
   }
 
   type Output1 = u32;
 
-  fn part_1(input: &Self::Input) -> Self::Output1 {
-    let transposed: Vec<Vec<u32>> = transpose(input);
-    let input_len = input.len();
-    let gamma_bits: Vec<u32> = transposed
-      .iter()
-      .map(|r| {
-        if r.iter().cloned().sum::<u32>() as usize > input_len / 2 {
+  fn part_1(input: &Self::Input) -> Result<Self::Output1, NoSolution> {
+    let input_len = input.height();
+    let gamma_bits: Vec<u32> = input
+      .columns()
+      .map(|c| {
+        if c.sum::<u32>() as usize > input_len / 2 {
           1
         } else {
           0
@@ -65,20 +61,68 @@ impl Day for Day03 {
       .collect();
     let gamma: u32 = parse_bits(&gamma_bits);
     let epsilon: u32 = parse_bits(&gamma_bits.iter().map(|x| 1 - x).collect::<Vec<u32>>());
-    gamma * epsilon
+    Ok(gamma * epsilon)
   }
 
   type Output2 = u32;
 
-  fn part_2(input: &Self::Input) -> Self::Output2 {
-    let oxygen_bits = &(0..input[0].len()).fold(input.to_owned(), |acc, i| {
+  fn part_2(input: &Self::Input) -> Result<Self::Output2, NoSolution> {
+    if input.is_empty() {
+      return Err(NoSolution(String::from("there are no numbers")));
+    }
+    let numbers: Vec<Vec<u32>> = input.rows().map(|r| r.to_vec()).collect();
+    let oxygen_bits = &(0..input.width()).fold(numbers.clone(), |acc, i| {
       filter_common(&acc, i, FilterMode::MostCommon)
     })[0];
-    let co2_bits = &(0..input[0].len()).fold(input.to_owned(), |acc, i| {
+    let co2_bits = &(0..input.width()).fold(numbers, |acc, i| {
       filter_common(&acc, i, FilterMode::LeastCommon)
     })[0];
     let oxygen = parse_bits(oxygen_bits);
     let co2 = parse_bits(co2_bits);
-    oxygen * co2
+    Ok(oxygen * co2)
+  }
+}
+
+impl Generate for Day03 {
+  fn generate<R: Rng>(rng: &mut R, scale: usize) -> String {
+    let count = 1000 * scale;
+    // The numbers need to be distinct, otherwise part 2 can filter out all of them
+    let mut width = 12;
+    while 1 << width < 2 * count {
+      width += 1;
+    }
+    sample(rng, 1 << width, count)
+      .iter()
+      .map(|n| format!("{:0width$b}", n, width = width))
+      .collect::<Vec<String>>()
+      .join("\n")
+  }
+}
+
+impl Print for Day03 {
+  fn print(input: &Self::Input) -> String {
+    input.to_string()
+  }
+}
+
+impl Explore for Day03 {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn solves_the_example() {
+    let example = "00100\n11110\n10110\n10111\n10101\n01111\n00111\n11100\n10000\n11001\n00010\n01010";
+    let (_, input) = Day03::parse(example).unwrap();
+    assert_eq!(Day03::part_1(&input), Ok(198));
+    assert_eq!(Day03::part_2(&input), Ok(230));
+  }
+
+  #[test]
+  fn keeps_numbers_that_all_share_a_bit() {
+    // Every number starts with a 1, so the least common first bit would filter out all of them
+    let (_, input) = Day03::parse("110\n100\n111\n101").unwrap();
+    assert_eq!(Day03::part_2(&input), Ok(7 * 4));
   }
 }
@@ -1,23 +1,23 @@
-use nom::character::complete::{char, i32, newline, space0};
-use nom::combinator::{map as pmap, opt};
-use nom::multi::{count, many1, separated_list0};
-use nom::sequence::pair;
+use crate::parser::{blank_line, blocks, csv_ints, print_list};
+use nom::character::complete::{i32, newline, space0};
+use nom::combinator::{map as pmap, map_res};
+use nom::multi::{count, separated_list1};
+use nom::sequence::{pair, separated_pair};
 use nom::IResult;
-use std::convert::TryInto;
+use rand::seq::index::sample;
+use rand::seq::SliceRandom;
+use rand::Rng;
 
-use crate::days::Day;
+use crate::days::{Day, Explore, Generate, NoSolution, Print};
 
 pub struct Day04;
 
 fn parse_row(input: &str) -> IResult<&str, [i32; 5]> {
-  let (cont, res) = count(pmap(pair(space0, i32), |x| x.1), 5)(input)?;
-  let arr = res.try_into().unwrap();
-  Ok((cont, arr))
+  map_res(count(pmap(pair(space0, i32), |x| x.1), 5), Vec::try_into)(input)
 }
 
 fn parse_board(input: &str) -> IResult<&str, [[i32; 5]; 5]> {
-  let (cont, res) = count(pmap(pair(parse_row, opt(newline)), |x| x.0), 5)(input)?;
-  Ok((cont, res.try_into().unwrap()))
+  map_res(separated_list1(newline, parse_row), Vec::try_into)(input)
 }
 
 fn setup_boards(boards: &[[[i32; 5]; 5]]) -> Vec<[[(i32, bool); 5]; 5]> {
@@ -57,14 +57,12 @@ impl Day for Day04 {
   type Input = (Vec<i32>, Vec<[[i32; 5]; 5]>);
 
   fn parse(input: &str) -> IResult<&str, Self::Input> {
-    let (cont, (nums, _)) = pair(separated_list0(char(','), i32), count(newline, 2))(input)?;
-    let (cont, boards) = separated_list0(many1(newline), parse_board)(cont)?;
-    Ok((cont, (nums, boards)))
+    separated_pair(csv_ints, blank_line, blocks(parse_board))(input)
   }
 
   type Output1 = i32;
 
-  fn part_1((nums, boards): &Self::Input) -> Self::Output1 {
+  fn part_1((nums, boards): &Self::Input) -> Result<Self::Output1, NoSolution> {
     let mut boards_results = setup_boards(boards);
 
     for n in nums {
@@ -72,32 +70,83 @@ impl Day for Day04 {
 
       for b in &boards_results {
         if has_won(b) {
-          return non_marked_sum(b) * n;
+          return Ok(non_marked_sum(b) * n);
         }
       }
     }
 
-    panic!("No full board found")
+    Err(NoSolution(String::from("no board wins")))
   }
 
   type Output2 = i32;
 
-  fn part_2((nums, boards): &Self::Input) -> Self::Output2 {
+  fn part_2((nums, boards): &Self::Input) -> Result<Self::Output2, NoSolution> {
     let mut boards_results = setup_boards(boards);
 
     for n in nums {
       update_boards(&mut boards_results, *n);
 
       if boards_results.len() > 1 {
-        boards_results = boards_results
-          .into_iter()
-          .filter(|b| !has_won(b))
-          .collect();
-      } else if has_won(&boards_results[0]) {
-        return non_marked_sum(&boards_results[0]) * n;
+        boards_results.retain(|b| !has_won(b));
+      } else if let Some(last) = boards_results.first() {
+        if has_won(last) {
+          return Ok(non_marked_sum(last) * n);
+        }
       }
     }
 
-    panic!("No full board found")
+    Err(NoSolution(String::from("no single board wins last")))
+  }
+}
+
+impl Generate for Day04 {
+  fn generate<R: Rng>(rng: &mut R, scale: usize) -> String {
+    let mut nums: Vec<u32> = (0..100).collect();
+    nums.shuffle(rng);
+    let nums_line = nums
+      .iter()
+      .map(|n| n.to_string())
+      .collect::<Vec<String>>()
+      .join(",");
+    let boards = (0..100 * scale).map(|_| {
+      let board = sample(rng, 100, 25).into_vec();
+      board
+        .chunks(5)
+        .map(|row| {
+          row
+            .iter()
+            .map(|n| format!("{:>2}", n))
+            .collect::<Vec<String>>()
+            .join(" ")
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+    });
+    let mut res = vec![nums_line];
+    res.extend(boards);
+    res.join("\n\n")
   }
 }
+
+impl Print for Day04 {
+  fn print(input: &Self::Input) -> String {
+    let (nums, boards) = input;
+    let mut res = vec![print_list(nums, ",")];
+    res.extend(boards.iter().map(|board| {
+      board
+        .iter()
+        .map(|row| {
+          row
+            .iter()
+            .map(|n| format!("{:>2}", n))
+            .collect::<Vec<String>>()
+            .join(" ")
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+    }));
+    res.join("\n\n")
+  }
+}
+
+impl Explore for Day04 {}
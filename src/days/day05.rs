@@ -1,89 +1,95 @@
-use itertools::concat;
+use crate::geometry::Vec2;
+use crate::parser::lines;
 use nom::bytes::complete::tag;
-use nom::character::complete::{i32, newline};
-use nom::multi::separated_list0;
+use nom::character::complete::i32;
 use nom::sequence::tuple;
 use nom::IResult;
-use std::cmp::{max, min};
+use rand::Rng;
 use std::collections::HashMap;
 
-use crate::days::Day;
+use crate::days::{Day, Explore, Generate, NoSolution, Print};
 
 pub struct Day05;
 
-type Line = ((i32, i32), (i32, i32));
+type Line = (Vec2<i32>, Vec2<i32>);
 
 fn parse_line(input: &str) -> IResult<&str, Line> {
   let (cont, (x1, _, y1, _, x2, _, y2)) =
     tuple((i32, tag(","), i32, tag(" -> "), i32, tag(","), i32))(input)?;
-  Ok((cont, ((x1, y1), (x2, y2))))
+  Ok((cont, (Vec2::new(x1, y1), Vec2::new(x2, y2))))
+}
+
+fn is_straight((a, b): &Line) -> bool {
+  a.x == b.x || a.y == b.y
+}
+
+// Every point of a horizontal, vertical or diagonal line, including both ends
+fn points((a, b): &Line) -> impl Iterator<Item = Vec2<i32>> {
+  let (a, d) = (*a, *b - *a);
+  let length = d.x.abs().max(d.y.abs());
+  (0..=length).map(move |i| a + d.signum() * i)
+}
+
+fn count_overlaps<'a>(lines: impl Iterator<Item = &'a Line>) -> usize {
+  let mut points_count: HashMap<Vec2<i32>, i32> = HashMap::new();
+  for p in lines.flat_map(points) {
+    *points_count.entry(p).or_default() += 1;
+  }
+  points_count.values().filter(|n| **n > 1).count()
 }
 
 impl Day for Day05 {
   type Input = Vec<Line>;
 
   fn parse(input: &str) -> IResult<&str, Self::Input> {
-    separated_list0(newline, parse_line)(input)
+    lines(parse_line)(input)
   }
 
   type Output1 = usize;
 
-  fn part_1(input: &Self::Input) -> Self::Output1 {
-    let points: Vec<(i32, i32)> = concat(
-      input
-        .iter()
-        .filter(|((x1, y1), (x2, y2))| x1 == x2 || y1 == y2)
-        .map(|((x1, y1), (x2, y2))| {
-          if y1 == y2 {
-            (min(*x1, *x2)..=max(*x1, *x2))
-              .map(|x| (x, *y1))
-              .collect::<Vec<(i32, i32)>>()
-          } else {
-            (min(*y1, *y2)..=max(*y1, *y2))
-              .map(|y| (*x1, y))
-              .collect::<Vec<(i32, i32)>>()
-          }
-        }),
-    );
-
-    let mut points_count: HashMap<(i32, i32), i32> = HashMap::new();
-    for p in points {
-      *points_count.entry(p).or_default() += 1;
-    }
-
-    let answer = points_count.iter().filter(|(_, x)| x > &&1).count();
-
-    answer
+  fn part_1(input: &Self::Input) -> Result<Self::Output1, NoSolution> {
+    Ok(count_overlaps(input.iter().filter(|l| is_straight(l))))
   }
 
   type Output2 = usize;
 
-  fn part_2(input: &Self::Input) -> Self::Output2 {
-    let points: Vec<(i32, i32)> = concat(input.iter().cloned().map(|((x1, y1), (x2, y2))| {
-      if y1 == y2 {
-        (min(x1, x2)..=max(x1, x2))
-          .map(|x| (x, y1))
-          .collect::<Vec<(i32, i32)>>()
-      } else if x1 == x2 {
-        (min(y1, y2)..=max(y1, y2))
-          .map(|y| (x1, y))
-          .collect::<Vec<(i32, i32)>>()
-      } else {
-        let facx = if x2 > x1 { 1 } else { -1 };
-        let facy = if y2 > y1 { 1 } else { -1 };
-        (0..=(i32::abs(x2 - x1)))
-          .map(|d| (x1 + d * facx, y1 + d * facy))
-          .collect()
-      }
-    }));
-
-    let mut points_count: HashMap<(i32, i32), i32> = HashMap::new();
-    for p in points {
-      *points_count.entry(p).or_default() += 1;
-    }
-
-    let answer = points_count.iter().filter(|(_, x)| x > &&1).count();
-
-    answer
+  fn part_2(input: &Self::Input) -> Result<Self::Output2, NoSolution> {
+    Ok(count_overlaps(input.iter()))
   }
 }
+
+impl Generate for Day05 {
+  fn generate<R: Rng>(rng: &mut R, scale: usize) -> String {
+    (0..500 * scale)
+      .map(|_| {
+        // Lines are horizontal, vertical or diagonal at exactly 45 degrees
+        let (dx, dy) = [(1, 0), (0, 1), (1, 1), (1, -1)][rng.gen_range(0..4)];
+        let length = rng.gen_range(1..300);
+        let x1 = rng.gen_range(0..1000 - length * dx);
+        let y1 = match dy {
+          -1 => rng.gen_range(length..1000),
+          _ => rng.gen_range(0..1000 - length * dy),
+        };
+        let (x2, y2) = (x1 + length * dx, y1 + length * dy);
+        if rng.gen_bool(0.5) {
+          format!("{},{} -> {},{}", x1, y1, x2, y2)
+        } else {
+          format!("{},{} -> {},{}", x2, y2, x1, y1)
+        }
+      })
+      .collect::<Vec<String>>()
+      .join("\n")
+  }
+}
+
+impl Print for Day05 {
+  fn print(input: &Self::Input) -> String {
+    input
+      .iter()
+      .map(|(a, b)| format!("{} -> {}", a, b))
+      .collect::<Vec<String>>()
+      .join("\n")
+  }
+}
+
+impl Explore for Day05 {}
@@ -1,10 +1,10 @@
-use nom::bytes::complete::tag;
-use nom::character::complete::u8;
-use nom::multi::separated_list0;
+use crate::parser::{csv_ints, print_list};
 use nom::IResult;
+use rand::Rng;
+use std::borrow::Cow;
 use std::collections::HashMap;
 
-use crate::days::Day;
+use crate::days::{Day, Explore, Generate, NoSolution, Param, Print};
 
 pub struct Day06;
 
@@ -32,18 +32,45 @@ impl Day for Day06 {
   type Input = Vec<u8>;
 
   fn parse(input: &str) -> IResult<&str, Self::Input> {
-    separated_list0(tag(","), u8)(input)
+    csv_ints(input)
   }
 
   type Output1 = u64;
 
-  fn part_1(input: &Self::Input) -> Self::Output1 {
-    run(input, 80)
+  fn part_1(input: &Self::Input) -> Result<Self::Output1, NoSolution> {
+    Ok(run(input, 80))
   }
 
   type Output2 = u64;
 
-  fn part_2(input: &Self::Input) -> Self::Output2 {
-    run(input, 256)
+  fn part_2(input: &Self::Input) -> Result<Self::Output2, NoSolution> {
+    Ok(run(input, 256))
+  }
+}
+
+impl Generate for Day06 {
+  fn generate<R: Rng>(rng: &mut R, scale: usize) -> String {
+    (0..300 * scale)
+      .map(|_| rng.gen_range(1..=5).to_string())
+      .collect::<Vec<String>>()
+      .join(",")
+  }
+}
+
+impl Print for Day06 {
+  fn print(input: &Self::Input) -> String {
+    print_list(input, ",")
+  }
+}
+
+impl Explore for Day06 {
+  const PARAMS: &'static [Param] = &[Param {
+    name: Cow::Borrowed("days"),
+    part_1: 80,
+    part_2: 256,
+  }];
+
+  fn run_with(input: &Self::Input, values: &[usize]) -> Result<String, NoSolution> {
+    Ok(run(input, values[0] as u32).to_string())
   }
 }
@@ -1,10 +1,9 @@
+use crate::parser::{csv_ints, print_list};
 use itertools::sorted;
-use nom::bytes::complete::tag;
-use nom::character::complete::i32;
-use nom::multi::separated_list0;
 use nom::IResult;
+use rand::Rng;
 
-use crate::days::Day;
+use crate::days::{Day, Explore, Generate, NoSolution, Print};
 
 pub struct Day07;
 
@@ -12,26 +11,50 @@ impl Day for Day07 {
   type Input = Vec<i32>;
 
   fn parse(input: &str) -> IResult<&str, Self::Input> {
-    separated_list0(tag(","), i32)(input)
+    csv_ints(input)
   }
 
   type Output1 = i32;
 
-  fn part_1(input: &Self::Input) -> Self::Output1 {
+  fn part_1(input: &Self::Input) -> Result<Self::Output1, NoSolution> {
+    if input.is_empty() {
+      return Err(NoSolution(String::from("there are no crabs")));
+    }
     let s: Vec<i32> = sorted(input.to_owned()).collect();
     let med = s[s.len() / 2];
 
-    s.iter().map(|x| i32::abs(x - med)).sum::<i32>()
+    Ok(s.iter().map(|x| i32::abs(x - med)).sum::<i32>())
   }
 
   type Output2 = i32;
 
-  fn part_2(input: &Self::Input) -> Self::Output2 {
+  fn part_2(input: &Self::Input) -> Result<Self::Output2, NoSolution> {
+    if input.is_empty() {
+      return Err(NoSolution(String::from("there are no crabs")));
+    }
     let avg = input.iter().sum::<i32>() / input.iter().len() as i32;
 
-    input
+    let fuel = input
       .iter()
       .map(|x| (1..=i32::abs(x - avg)).sum::<i32>())
-      .sum::<i32>()
+      .sum::<i32>();
+    Ok(fuel)
   }
 }
+
+impl Generate for Day07 {
+  fn generate<R: Rng>(rng: &mut R, scale: usize) -> String {
+    (0..1000 * scale)
+      .map(|_| rng.gen_range(0..2000).to_string())
+      .collect::<Vec<String>>()
+      .join(",")
+  }
+}
+
+impl Print for Day07 {
+  fn print(input: &Self::Input) -> String {
+    print_list(input, ",")
+  }
+}
+
+impl Explore for Day07 {}
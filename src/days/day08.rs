@@ -1,11 +1,14 @@
+use crate::parser::lines;
 use nom::bytes::complete::tag;
-use nom::character::complete::{alpha1, newline, space1};
+use nom::character::complete::{alpha1, space1};
 use nom::multi::separated_list0;
 use nom::sequence::tuple;
 use nom::IResult;
+use rand::seq::SliceRandom;
+use rand::Rng;
 use std::collections::{HashMap, HashSet};
 
-use crate::days::Day;
+use crate::days::{Day, Explore, Generate, NoSolution, Print};
 
 pub struct Day08;
 
@@ -70,17 +73,20 @@ fn set_chars(chars: &mut HashSet<char>, word: &str) {
   }
 }
 
-fn find_match(input_set: &HashSet<Segment>, references: &[HashSet<Segment>]) -> i32 {
-  for i in 0..=9 {
-    if input_set.eq(&references[i]) {
-      return i as i32;
+fn find_match(
+  input_set: &HashSet<Segment>,
+  references: &[HashSet<Segment>],
+) -> Result<i32, NoSolution> {
+  for (i, reference) in references.iter().enumerate().take(10) {
+    if input_set.eq(reference) {
+      return Ok(i as i32);
     }
   }
 
-  panic!("No solution found")
+  Err(NoSolution(format!("{:?} is not a digit", input_set)))
 }
 
-fn run_line(i: &[String], o: &[String]) -> i32 {
+fn run_line(i: &[String], o: &[String]) -> Result<i32, NoSolution> {
   use Segment::*;
 
   let mut char_map: HashMap<char, HashSet<Segment>> = HashMap::new();
@@ -146,33 +152,48 @@ fn run_line(i: &[String], o: &[String]) -> i32 {
       _ => (),
     }
   }
-  let res: i32 = o
-    .iter()
-    .map(|o1| {
-      let mut o1_set = HashSet::new();
-      for c in o1.chars() {
-        for x in &char_map[&c] {
-          o1_set.insert(*x);
-        }
+  let mut res = 0;
+  for o1 in o {
+    let mut o1_set = HashSet::new();
+    for c in o1.chars() {
+      let segments = char_map
+        .get(&c)
+        .ok_or_else(|| NoSolution(format!("{} is not a wire", c)))?;
+      for x in segments {
+        o1_set.insert(*x);
       }
-      find_match(&o1_set, &nrs)
-    })
-    .fold(0, |acc, x| acc * 10 + x);
+    }
+    res = res * 10 + find_match(&o1_set, &nrs)?;
+  }
+
+  Ok(res)
+}
 
-  res
+// The segments of each digit on a correctly wired display
+const DIGITS: [&str; 10] = [
+  "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
+];
+
+fn scramble<R: Rng>(rng: &mut R, wiring: &[char], digit: usize) -> String {
+  let mut segments: Vec<char> = DIGITS[digit]
+    .chars()
+    .map(|c| wiring[c as usize - 'a' as usize])
+    .collect();
+  segments.shuffle(rng);
+  segments.into_iter().collect()
 }
 
 impl Day for Day08 {
   type Input = Vec<(Vec<String>, Vec<String>)>;
 
   fn parse(input: &str) -> IResult<&str, Self::Input> {
-    separated_list0(newline, parse_line)(input)
+    lines(parse_line)(input)
   }
 
   type Output1 = i32;
 
-  fn part_1(input: &Self::Input) -> Self::Output1 {
-    input
+  fn part_1(input: &Self::Input) -> Result<Self::Output1, NoSolution> {
+    let count = input
       .iter()
       .map(|(_, o)| {
         o.iter()
@@ -182,12 +203,50 @@ impl Day for Day08 {
           })
           .count() as i32
       })
-      .sum::<i32>()
+      .sum::<i32>();
+    Ok(count)
   }
 
   type Output2 = i32;
 
-  fn part_2(input: &Self::Input) -> Self::Output2 {
-    input.iter().map(|(i,o)| run_line(i,o)).sum::<i32>()
+  fn part_2(input: &Self::Input) -> Result<Self::Output2, NoSolution> {
+    input.iter().map(|(i,o)| run_line(i,o)).sum()
   }
 }
+
+impl Generate for Day08 {
+  fn generate<R: Rng>(rng: &mut R, scale: usize) -> String {
+    (0..200 * scale)
+      .map(|_| {
+        let mut wiring: Vec<char> = ('a'..='g').collect();
+        wiring.shuffle(rng);
+        let mut patterns: Vec<usize> = (0..10).collect();
+        patterns.shuffle(rng);
+        let patterns: Vec<String> = patterns
+          .into_iter()
+          .map(|d| scramble(rng, &wiring, d))
+          .collect();
+        let outputs: Vec<String> = (0..4)
+          .map(|_| {
+            let d = rng.gen_range(0..10);
+            scramble(rng, &wiring, d)
+          })
+          .collect();
+        format!("{} | {}", patterns.join(" "), outputs.join(" "))
+      })
+      .collect::<Vec<String>>()
+      .join("\n")
+  }
+}
+
+impl Print for Day08 {
+  fn print(input: &Self::Input) -> String {
+    input
+      .iter()
+      .map(|(patterns, outputs)| format!("{} | {}", patterns.join(" "), outputs.join(" ")))
+      .collect::<Vec<String>>()
+      .join("\n")
+  }
+}
+
+impl Explore for Day08 {}
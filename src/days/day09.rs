@@ -1,42 +1,22 @@
-use crate::parser::digit_grid;
+use crate::parser::{digit_grid, Grid, Pos};
 use nom::IResult;
+use rand::Rng;
 use std::collections::HashSet;
 
-use crate::days::Day;
+use crate::days::{Day, Explore, Generate, NoSolution, Print};
 
 pub struct Day09;
 
-fn find_low_points(input: &[Vec<u32>]) -> Vec<(usize, usize)> {
-  let height = input.len();
-  let width = input[0].len();
-  let mut res = vec![];
-  for y in 0..height {
-    for x in 0..width {
-      let val = input[y][x];
-      if (x == 0 || val < input[y][x - 1])
-        && (x == width - 1 || val < input[y][x + 1])
-        && (y == 0 || val < input[y - 1][x])
-        && (y == height - 1 || val < input[y + 1][x])
-      {
-        res.push((x, y));
-      }
-    }
-  }
-  res
+fn find_low_points(input: &Grid<u32>) -> Vec<Pos> {
+  input
+    .positions()
+    .filter(|p| input.neighbors4(*p).all(|n| input[*p] < input[n]))
+    .collect()
 }
 
-fn expand_basin(
-  basin_points: &mut HashSet<(usize, usize)>,
-  input: &[Vec<u32>],
-  point: (usize, usize),
-) {
-  let (x, y) = point;
-  let height = input.len();
-  let width = input[0].len();
-  let adjacents = vec![(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)];
-  for p in adjacents {
-    let (x2, y2) = p;
-    if x2 < width && y2 < height && input[y2][x2] < 9 && !basin_points.contains(&p) {
+fn expand_basin(basin_points: &mut HashSet<Pos>, input: &Grid<u32>, point: Pos) {
+  for p in input.neighbors4(point) {
+    if input[p] < 9 && !basin_points.contains(&p) {
       basin_points.insert(p);
       expand_basin(basin_points, input, p);
     }
@@ -44,7 +24,7 @@ fn expand_basin(
 }
 
 impl Day for Day09 {
-  type Input = Vec<Vec<u32>>;
+  type Input = Grid<u32>;
 
   fn parse(input: &str) -> IResult<&str, Self::Input> {
     digit_grid(input)
@@ -52,17 +32,18 @@ impl Day for Day09 {
 
   type Output1 = u32;
 
-  fn part_1(input: &Self::Input) -> Self::Output1 {
+  fn part_1(input: &Self::Input) -> Result<Self::Output1, NoSolution> {
     let low_points = find_low_points(input);
-    low_points
+    let risk = low_points
       .iter()
-      .map(|(x, y)| input[*y][*x] + 1)
-      .sum::<u32>()
+      .map(|p| input[*p] + 1)
+      .sum::<u32>();
+    Ok(risk)
   }
 
   type Output2 = u32;
 
-  fn part_2(input: &Self::Input) -> Self::Output2 {
+  fn part_2(input: &Self::Input) -> Result<Self::Output2, NoSolution> {
     let low_points = find_low_points(input);
 
     let mut basin_sizes: Vec<u32> = low_points
@@ -76,6 +57,37 @@ impl Day for Day09 {
 
     basin_sizes.sort_by(|a, b| b.cmp(a));
 
-    basin_sizes[0..=2].iter().product::<u32>()
+    if basin_sizes.len() < 3 {
+      return Err(NoSolution(String::from("there are fewer than three basins")));
+    }
+    Ok(basin_sizes[0..=2].iter().product::<u32>())
   }
 }
+
+impl Generate for Day09 {
+  fn generate<R: Rng>(rng: &mut R, scale: usize) -> String {
+    let size = 100 * scale;
+    (0..size)
+      .map(|_| {
+        (0..size)
+          .map(|_| {
+            if rng.gen_bool(0.25) {
+              '9'
+            } else {
+              rng.gen_range('0'..='8')
+            }
+          })
+          .collect::<String>()
+      })
+      .collect::<Vec<String>>()
+      .join("\n")
+  }
+}
+
+impl Print for Day09 {
+  fn print(input: &Self::Input) -> String {
+    input.to_string()
+  }
+}
+
+impl Explore for Day09 {}
@@ -1,6 +1,6 @@
 use nom::IResult;
 
-use crate::days::Day;
+use crate::days::{Day, Explore, Generate, NoSolution, Print};
 
 pub struct Day10;
 
@@ -12,6 +12,7 @@ enum Delimiter {
   Tag,
 }
 use Delimiter::*;
+use rand::Rng;
 
 fn check_line(line: &str) -> Result<Vec<Delimiter>, Delimiter> {
   let mut levels: Vec<Delimiter> = vec![];
@@ -45,6 +46,40 @@ fn check_line(line: &str) -> Result<Vec<Delimiter>, Delimiter> {
   Ok(levels)
 }
 
+const PAIRS: [(char, char); 4] = [('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')];
+
+// Generates either an incomplete or a corrupted line
+fn generate_line<R: Rng>(rng: &mut R) -> String {
+  let mut res = String::new();
+  let mut expected: Vec<char> = vec![];
+  for _ in 0..rng.gen_range(80..100) {
+    match expected.pop() {
+      Some(close) if rng.gen_bool(0.4) => res.push(close),
+      opt_close => {
+        expected.extend(opt_close);
+        let (open, close) = PAIRS[rng.gen_range(0..4)];
+        res.push(open);
+        expected.push(close);
+      }
+    }
+  }
+  if expected.is_empty() {
+    let (open, close) = PAIRS[rng.gen_range(0..4)];
+    res.push(open);
+    expected.push(close);
+  }
+  if rng.gen_bool(0.5) {
+    let close = expected.last().unwrap();
+    let wrong: Vec<char> = PAIRS.iter().map(|p| p.1).filter(|c| c != close).collect();
+    res.push(wrong[rng.gen_range(0..wrong.len())]);
+    for _ in 0..rng.gen_range(0..10) {
+      let (open, close) = PAIRS[rng.gen_range(0..4)];
+      res.push(if rng.gen_bool(0.5) { open } else { close });
+    }
+  }
+  res
+}
+
 impl Day for Day10 {
   type Input = Vec<String>;
 
@@ -56,8 +91,8 @@ impl Day for Day10 {
 
   type Output1 = i32;
 
-  fn part_1(input: &Self::Input) -> Self::Output1 {
-    input
+  fn part_1(input: &Self::Input) -> Result<Self::Output1, NoSolution> {
+    let score = input
       .iter()
       .map(|l| match check_line(l) {
         Ok(_) => 0,
@@ -66,12 +101,13 @@ impl Day for Day10 {
         Err(Curly) => 1197,
         Err(Tag) => 25137,
       })
-      .sum::<i32>()
+      .sum::<i32>();
+    Ok(score)
   }
 
   type Output2 = i64;
 
-  fn part_2(input: &Self::Input) -> Self::Output2 {
+  fn part_2(input: &Self::Input) -> Result<Self::Output2, NoSolution> {
     let mut scores: Vec<i64> = input
       .iter()
       .map(|l| check_line(l))
@@ -92,6 +128,26 @@ impl Day for Day10 {
 
     scores.sort_unstable();
 
-    scores[scores.len() / 2]
+    if scores.is_empty() {
+      return Err(NoSolution(String::from("there are no incomplete lines")));
+    }
+    Ok(scores[scores.len() / 2])
+  }
+}
+
+impl Generate for Day10 {
+  fn generate<R: Rng>(rng: &mut R, scale: usize) -> String {
+    (0..100 * scale)
+      .map(|_| generate_line(rng))
+      .collect::<Vec<String>>()
+      .join("\n")
   }
 }
+
+impl Print for Day10 {
+  fn print(input: &Self::Input) -> String {
+    input.join("\n")
+  }
+}
+
+impl Explore for Day10 {}
@@ -1,109 +1,171 @@
-use nom::character::complete::{digit1, newline};
-use nom::multi::separated_list1;
+use crate::parser::{digit, grid, Grid, Pos};
+use nom::combinator::map as pmap;
 use nom::IResult;
-use std::cmp::min;
+use rand::Rng;
+use std::fmt::{self, Display, Formatter};
+use std::iter::{from_fn, once};
 
-use crate::days::Day;
+use crate::days::{Colour, Day, Explore, Frame, Generate, NoSolution, Print, Visualize};
 
 pub struct Day11;
 
-const WIDTH: usize = 10;
-const HEIGHT: usize = 10;
-
-fn parse_line(input: &str) -> IResult<&str, [Octopus; WIDTH]> {
-  let (cont, digits_str) = digit1(input)?;
-  let res: Vec<Octopus> = digits_str
-    .chars()
-    .map(|c| Octopus {
-      level: c.to_digit(10).unwrap(),
-      has_flashed: false,
-    })
-    .collect();
-  Ok((cont, res.try_into().unwrap()))
-}
-
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Octopus {
   level: u32,
   has_flashed: bool,
 }
 
-fn get_min_1(i: usize) -> usize {
-  match i {
-    0 => 0,
-    _ => i - 1,
+impl Display for Octopus {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    write!(f, "{}", self.level)
   }
 }
 
-fn flash(input: &mut [[Octopus; WIDTH]; HEIGHT], x: usize, y: usize) {
-  input[y][x].has_flashed = true;
-  for y2 in get_min_1(y)..=min(HEIGHT - 1, y + 1) {
-    for x2 in get_min_1(x)..=min(WIDTH - 1, x + 1) {
-      if !(y2 == y && x2 == x) {
-        input[y2][x2].level += 1;
-        if input[y2][x2].level > 9 && !input[y2][x2].has_flashed {
-          flash(input, x2, y2);
-        }
-      }
+fn parse_octopus(input: &str) -> IResult<&str, Octopus> {
+  pmap(digit, |level| Octopus {
+    level,
+    has_flashed: false,
+  })(input)
+}
+
+fn flash(input: &mut Grid<Octopus>, pos: Pos) {
+  input[pos].has_flashed = true;
+  for n in input.neighbors8(pos) {
+    input[n].level += 1;
+    if input[n].level > 9 && !input[n].has_flashed {
+      flash(input, n);
     }
   }
 }
 
-fn step(input: &mut [[Octopus; WIDTH]; HEIGHT]) -> u32 {
-
-  for row in &mut *input {
-    for l in row {
-      l.level += 1;
-    }
+fn step(input: &mut Grid<Octopus>) -> u32 {
+  for l in input.iter_mut() {
+    l.level += 1;
   }
 
-  for y in 0..HEIGHT {
-    for x in 0..WIDTH {
-      if input[y][x].level > 9 && !input[y][x].has_flashed {
-        flash(input, x, y);
-      }
+  for pos in input.positions() {
+    if input[pos].level > 9 && !input[pos].has_flashed {
+      flash(input, pos);
     }
   }
 
   let mut flash_count = 0;
-  for row in input {
-    for l in row {
-      if l.has_flashed {
-        l.level = 0;
-        flash_count += 1;
-        l.has_flashed = false;
-      }
+  for l in input.iter_mut() {
+    if l.has_flashed {
+      l.level = 0;
+      flash_count += 1;
+      l.has_flashed = false;
     }
   }
   flash_count
 }
 
+fn octopus_colour(o: &Octopus) -> Colour {
+  match o.level {
+    // Octopuses that just flashed
+    0 => (255, 255, 255),
+    l => {
+      let v = (l * 20) as u8;
+      (v / 2, v / 2, v)
+    }
+  }
+}
+
+fn to_frame(input: &Grid<Octopus>) -> Frame {
+  input
+    .rows()
+    .map(|row| {
+      row
+        .iter()
+        .map(|o| (char::from_digit(o.level, 10).unwrap(), Some(octopus_colour(o))))
+        .collect()
+    })
+    .collect()
+}
+
 impl Day for Day11 {
-  type Input = [[Octopus; WIDTH]; HEIGHT];
+  type Input = Grid<Octopus>;
 
   fn parse(input: &str) -> IResult<&str, Self::Input> {
-    let (cont, arrs) = separated_list1(newline, parse_line)(input)?;
-    Ok((cont, arrs.try_into().unwrap()))
+    grid(parse_octopus)(input)
   }
 
   type Output1 = u32;
 
-  fn part_1(input: &Self::Input) -> Self::Output1 {
+  fn part_1(input: &Self::Input) -> Result<Self::Output1, NoSolution> {
     let mut input: Self::Input = input.clone();
-    (0..100).fold(0, |acc, _| acc + step(&mut input))
+    Ok((0..100).fold(0, |acc, _| acc + step(&mut input)))
   }
 
   type Output2 = u32;
 
-  fn part_2(input: &Self::Input) -> Self::Output2 {
+  fn part_2(input: &Self::Input) -> Result<Self::Output2, NoSolution> {
     let mut input: Self::Input = input.clone();
     let mut s = 0;
     loop {
       s += 1;
       let flashes = step(&mut input);
-      if flashes as usize == WIDTH * HEIGHT {
-        return s;
+      if flashes as usize == input.len() {
+        return Ok(s);
       }
     }
   }
 }
+
+// Some grids never synchronise, so the animation stops after this many steps
+const MAX_STEPS: usize = 1000;
+
+impl Visualize for Day11 {
+  // Steps until all octopuses flash at the same time, or until `MAX_STEPS`
+  fn frames(input: &Self::Input) -> Box<dyn Iterator<Item = Frame> + '_> {
+    let mut state = input.clone();
+    let mut synchronised = false;
+    let steps = from_fn(move || {
+      if synchronised {
+        None
+      } else {
+        synchronised = step(&mut state) as usize == input.len();
+        Some(to_frame(&state))
+      }
+    });
+    Box::new(once(to_frame(input)).chain(steps.take(MAX_STEPS)))
+  }
+}
+
+impl Generate for Day11 {
+  fn generate<R: Rng>(rng: &mut R, scale: usize) -> String {
+    let size = 10 * scale;
+    (0..size)
+      .map(|_| (0..size).map(|_| rng.gen_range('0'..='9')).collect::<String>())
+      .collect::<Vec<String>>()
+      .join("\n")
+  }
+}
+
+impl Print for Day11 {
+  fn print(input: &Self::Input) -> String {
+    input.to_string()
+  }
+}
+
+impl Explore for Day11 {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn frames_end_for_grids_that_never_synchronise() {
+    let (_, input) = Day11::parse("002").unwrap();
+    assert_eq!(Day11::frames(&input).count(), MAX_STEPS + 1);
+  }
+
+  #[test]
+  fn steps_grids_of_any_size() {
+    let (_, mut input) = Day11::parse("11111\n19991\n19191\n19991\n11111").unwrap();
+    assert_eq!(step(&mut input), 9);
+    assert_eq!(input.to_string(), "34543\n40004\n50005\n40004\n34543");
+    assert_eq!(step(&mut input), 0);
+    assert_eq!(input.to_string(), "45654\n51115\n61116\n51115\n45654");
+  }
+}
@@ -1,38 +1,29 @@
+use crate::graph::{Graph, NodeId};
+use crate::parser::lines;
 use nom::bytes::complete::tag;
-use nom::character::complete::{alpha1, newline};
-use nom::multi::separated_list0;
+use nom::character::complete::alpha1;
 use nom::sequence::tuple;
 use nom::IResult;
-use std::collections::HashMap;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 
-use crate::days::Day;
+use crate::days::{Day, Explore, Generate, NoSolution, Param, Print};
 
 pub struct Day12;
 
-const START: u64 = 1;
-const END: u64 = 2;
-
-fn str_to_cave<'a>(
-  keys_map: &mut HashMap<&'a str, u64>,
-  max_value: &mut u64,
-  input: &'a str,
-) -> u64 {
-  match input {
-    "start" => START,
-    "end" => END,
-    _ => {
-      let is_small = input.chars().next().unwrap().is_lowercase();
-      let key = match keys_map.get(input) {
-        Some(k) => *k,
-        None => {
-          *max_value <<= 1;
-          let key = *max_value + if is_small { 1 } else { 0 };
-          keys_map.insert(input, key);
-          key
-        }
-      };
-      key
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cave {
+  Small,
+  Big,
+}
+
+fn cave_kind(label: &str) -> Cave {
+  if label.chars().next().is_some_and(|c| c.is_lowercase()) {
+    Cave::Small
+  } else {
+    Cave::Big
   }
 }
 
@@ -41,90 +32,376 @@ fn parse_line(input: &str) -> IResult<&str, (&str, &str)> {
   Ok((cont, (a, b)))
 }
 
-fn make_map_key(c: u64, visited: u64, has_repeated_small: u64) -> u64 {
-  c << 25 | has_repeated_small << 24 | visited
-}
-
-fn run(
-  c: &u64,
-  hm: &HashMap<u64, Vec<u64>>,
-  res_map: &mut HashMap<u64, usize>,
-  visited: u64,
-  has_repeated_small: u64, // 1 as true, 0 as false
-) -> usize {
-  let nodes = hm.get(c).unwrap();
-  let mut res = 0;
-  if let Some(x) = res_map.get(&make_map_key(*c, visited, has_repeated_small)) {
-    return *x;
-  }
-  for c in nodes {
-    match c {
-      1 => {} //start
-      2 => {
-        //end
-        res += 1;
-      }
-      k => {
-        if k & 1 == 1 {
-          // Remove small sign, just keep the ID
-          let uk = k ^ 1;
-          //cave is small
-          if visited & uk != uk {
-            //this node has not been visited
-            let path = visited | uk;
-            let ress = run(c, hm, res_map, path, has_repeated_small);
-            res += ress;
-          } else if has_repeated_small == 0 {
-            //This node has been visited, but we can repeat it
-            let path = visited | uk;
-            let ress = run(c, hm, res_map, path, 1);
-            res += ress;
-          }
+pub fn build_graph(edges: &[(String, String)]) -> Graph<Cave> {
+  let mut graph = Graph::new();
+  for (a, b) in edges {
+    let a = graph.intern(a, cave_kind);
+    let b = graph.intern(b, cave_kind);
+    graph.add_edge(a, b);
+  }
+  graph
+}
+
+// Small caves are drawn as circles, big ones as boxes
+pub fn to_dot(edges: &[(String, String)]) -> String {
+  build_graph(edges).to_dot(|cave| match cave {
+    Cave::Small => String::new(),
+    Cave::Big => String::from("shape=box"),
+  })
+}
+
+// Which caves a path may visit, and how often. Without a budget, small caves may be visited once
+// and big caves any number of times. The path always starts in the start cave, and never returns
+// there.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Rules {
+  // The number of visits allowed for specific caves
+  pub budgets: HashMap<String, usize>,
+  // The number of small caves that may be visited once more than their budget
+  pub repeats: usize,
+  // Caves no path may enter
+  pub forbidden: HashSet<String>,
+}
+
+impl Rules {
+  // Part 1 visits small caves at most once
+  pub fn part_1() -> Rules {
+    Rules::default()
+  }
+
+  // Part 2 may visit a single small cave twice
+  pub fn part_2() -> Rules {
+    Rules {
+      repeats: 1,
+      ..Rules::default()
+    }
+  }
+}
+
+// The number of visits to every cave so far, and how many small caves may still be visited once
+// more than their budget
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct PathState {
+  visits: Vec<usize>,
+  repeats: usize,
+}
+
+pub struct CaveSystem {
+  caves: Graph<Cave>,
+  start: NodeId,
+  end: NodeId,
+  // The number of visits allowed to every cave, None if there is no limit
+  limits: Vec<Option<usize>>,
+  repeats: usize,
+}
+
+impl CaveSystem {
+  pub fn new(edges: &[(String, String)], rules: &Rules) -> Result<CaveSystem, NoSolution> {
+    let caves = build_graph(edges);
+    let (start, end) = match (caves.id("start"), caves.id("end")) {
+      (Some(start), Some(end)) => (start, end),
+      _ => return Err(NoSolution(String::from("there is no start or no end cave"))),
+    };
+    let limits = caves
+      .nodes()
+      .map(|id| {
+        let label = caves.label(id);
+        if rules.forbidden.contains(label) {
+          Some(0)
+        } else if id == start {
+          Some(1)
+        } else if let Some(budget) = rules.budgets.get(label) {
+          Some(*budget)
         } else {
-          //cave is big
-          let ress = run(c, hm, res_map, visited, has_repeated_small);
-          res += ress;
+          match caves.attr(id) {
+            Cave::Small => Some(1),
+            Cave::Big => None,
+          }
         }
-      }
+      })
+      .collect();
+    Ok(CaveSystem {
+      caves,
+      start,
+      end,
+      limits,
+      repeats: rules.repeats,
+    })
+  }
+
+  fn initial_state(&self) -> PathState {
+    let mut visits = vec![0; self.caves.len()];
+    visits[self.start] = 1;
+    PathState {
+      visits,
+      repeats: self.repeats,
+    }
+  }
+
+  fn step(&self, state: &PathState, next: NodeId) -> Option<PathState> {
+    let limit = match self.limits[next] {
+      Some(limit) => limit,
+      None => return Some(state.clone()),
+    };
+    let visits = state.visits[next];
+    let mut res = state.clone();
+    if visits < limit {
+      res.visits[next] += 1;
+      Some(res)
+    } else if visits == limit
+      && limit > 0
+      && next != self.start
+      && *self.caves.attr(next) == Cave::Small
+      && state.repeats > 0
+    {
+      res.visits[next] += 1;
+      res.repeats -= 1;
+      Some(res)
+    } else {
+      None
+    }
+  }
+
+  pub fn count_paths(&self) -> usize {
+    let state = self.initial_state();
+    self
+      .caves
+      .count_paths(self.start, self.end, state, |s, next| self.step(s, next))
+  }
+
+  // The paths as the labels of their caves separated by commas, like "start,A,b,end", found
+  // lazily
+  pub fn paths(&self) -> impl Iterator<Item = String> + '_ {
+    let state = self.initial_state();
+    self
+      .caves
+      .paths(self.start, self.end, state, |s, next| self.step(s, next))
+      .map(|path| self.caves.path_labels(&path).join(","))
+  }
+
+  // The same paths, those through the fewest caves first
+  pub fn shortest_paths(&self) -> impl Iterator<Item = String> + '_ {
+    let state = self.initial_state();
+    self
+      .caves
+      .shortest_paths(self.start, self.end, state, |s, next| self.step(s, next))
+      .map(|path| self.caves.path_labels(&path).join(","))
+  }
+}
+
+fn generate_name<R: Rng>(rng: &mut R, names: &mut HashSet<String>, big: bool) -> String {
+  loop {
+    let name: String = (0..2).map(|_| rng.gen_range('a'..='z')).collect();
+    let name = if big { name.to_uppercase() } else { name };
+    if names.insert(name.clone()) {
+      return name;
     }
   }
-  res_map.insert(make_map_key(*c, visited, has_repeated_small), res);
-  res
 }
 
 impl Day for Day12 {
-  type Input = HashMap<u64, Vec<u64>>;
+  type Input = Vec<(String, String)>;
 
   fn parse(input: &str) -> IResult<&str, Self::Input> {
-    let (cont, list) = separated_list0(newline, parse_line)(input)?;
-    let mut keys: HashMap<&str, u64> = HashMap::new();
-    let mut hm: HashMap<u64, Vec<u64>> = HashMap::new();
-
-    let mut max_value = 4;
-
-    for (s1, s2) in list {
-      let c1 = str_to_cave(&mut keys, &mut max_value, s1);
-      let c2 = str_to_cave(&mut keys, &mut max_value, s2);
-      let entry1 = hm.entry(c1).or_default();
-      entry1.push(c2);
-      let entry2 = hm.entry(c2).or_default();
-      entry2.push(c1);
-    }
-
-    Ok((cont, hm))
+    let (cont, list) = lines(parse_line)(input)?;
+    let edges = list
+      .into_iter()
+      .map(|(a, b)| (a.to_owned(), b.to_owned()))
+      .collect();
+    Ok((cont, edges))
   }
 
   type Output1 = usize;
 
-  fn part_1(input: &Self::Input) -> Self::Output1 {
-    let mut res_map = HashMap::new();
-    run(&START, input, &mut res_map, 0, 1)
+  fn part_1(input: &Self::Input) -> Result<Self::Output1, NoSolution> {
+    Ok(CaveSystem::new(input, &Rules::part_1())?.count_paths())
   }
 
   type Output2 = usize;
 
-  fn part_2(input: &Self::Input) -> Self::Output2 {
-    let mut res_map = HashMap::new();
-    run(&START, input, &mut res_map, 0, 0)
+  fn part_2(input: &Self::Input) -> Result<Self::Output2, NoSolution> {
+    Ok(CaveSystem::new(input, &Rules::part_2())?.count_paths())
+  }
+}
+
+impl Generate for Day12 {
+  fn generate<R: Rng>(rng: &mut R, scale: usize) -> String {
+    let mut names = HashSet::new();
+    let mut caves = vec![String::from("start"), String::from("end")];
+    for _ in 0..6 * scale {
+      caves.push(generate_name(rng, &mut names, false));
+    }
+    let first_big = caves.len();
+    for _ in 0..3 * scale {
+      caves.push(generate_name(rng, &mut names, true));
+    }
+    let is_big = |i: usize| i >= first_big;
+
+    let mut edges: HashSet<(usize, usize)> = HashSet::new();
+    // Make sure both start and end can be reached
+    edges.insert((0, rng.gen_range(2..caves.len())));
+    edges.insert((1, rng.gen_range(2..caves.len())));
+    while edges.len() < 22 * scale {
+      let a = rng.gen_range(0..caves.len());
+      let b = rng.gen_range(0..caves.len());
+      // Two connected big caves would allow infinitely many paths
+      if a < b && !(a == 0 && b == 1) && !(is_big(a) && is_big(b)) {
+        edges.insert((a, b));
+      }
+    }
+    let mut edges: Vec<(usize, usize)> = edges.into_iter().collect();
+    edges.sort_unstable();
+    edges.shuffle(rng);
+    edges
+      .iter()
+      .map(|(a, b)| format!("{}-{}", caves[*a], caves[*b]))
+      .collect::<Vec<String>>()
+      .join("\n")
+  }
+}
+
+impl Print for Day12 {
+  fn print(input: &Self::Input) -> String {
+    input
+      .iter()
+      .map(|(a, b)| format!("{}-{}", a, b))
+      .collect::<Vec<String>>()
+      .join("\n")
+  }
+}
+
+impl Explore for Day12 {
+  const PARAMS: &'static [Param] = &[
+    Param {
+      name: Cow::Borrowed("repeats"),
+      part_1: 0,
+      part_2: 1,
+    },
+    // The number of paths to list before the count
+    Param {
+      name: Cow::Borrowed("paths"),
+      part_1: 0,
+      part_2: 0,
+    },
+    // Whether to list the shortest paths first instead
+    Param {
+      name: Cow::Borrowed("shortest"),
+      part_1: 0,
+      part_2: 0,
+    },
+  ];
+
+  // Every small cave also gets a budget_<cave> parameter, and every cave a forbid_<cave> one, which
+  // forbids it when set to 1
+  fn params(input: &Self::Input) -> Vec<Param> {
+    let caves = build_graph(input);
+    let mut params = Self::PARAMS.to_vec();
+    for id in caves.nodes() {
+      let label = caves.label(id);
+      if label == "start" || label == "end" {
+        continue;
+      }
+      if *caves.attr(id) == Cave::Small {
+        params.push(Param {
+          name: Cow::Owned(format!("budget_{}", label)),
+          part_1: 1,
+          part_2: 1,
+        });
+      }
+      params.push(Param {
+        name: Cow::Owned(format!("forbid_{}", label)),
+        part_1: 0,
+        part_2: 0,
+      });
+    }
+    params
+  }
+
+  fn run_with(input: &Self::Input, values: &[usize]) -> Result<String, NoSolution> {
+    let mut rules = Rules {
+      repeats: values[0],
+      ..Rules::default()
+    };
+    for (param, value) in Self::params(input).iter().zip(values) {
+      if let Some(cave) = param.name.strip_prefix("budget_") {
+        rules.budgets.insert(cave.to_string(), *value);
+      } else if let Some(cave) = param.name.strip_prefix("forbid_") {
+        if *value != 0 {
+          rules.forbidden.insert(cave.to_string());
+        }
+      }
+    }
+    let caves = CaveSystem::new(input, &rules)?;
+    let mut lines: Vec<String> = if values[2] == 0 {
+      caves.paths().take(values[1]).collect()
+    } else {
+      caves.shortest_paths().take(values[1]).collect()
+    };
+    lines.push(caves.count_paths().to_string());
+    Ok(lines.join("\n"))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const EXAMPLE: &str = "start-A\nstart-b\nA-c\nA-b\nb-d\nA-end\nb-end";
+
+  fn count(rules: &Rules) -> usize {
+    let (_, edges) = Day12::parse(EXAMPLE).unwrap();
+    CaveSystem::new(&edges, rules).unwrap().count_paths()
+  }
+
+  #[test]
+  fn counts_the_paths_of_both_parts() {
+    assert_eq!(count(&Rules::part_1()), 10);
+    assert_eq!(count(&Rules::part_2()), 36);
+  }
+
+  #[test]
+  fn lists_the_paths_it_counts() {
+    let (_, edges) = Day12::parse(EXAMPLE).unwrap();
+    let caves = CaveSystem::new(&edges, &Rules::part_1()).unwrap();
+    let paths: HashSet<String> = caves.paths().collect();
+    assert_eq!(paths.len(), 10);
+    assert!(paths.contains("start,A,b,A,end"));
+    assert!(paths.contains("start,b,A,c,A,end"));
+    assert!(!paths.contains("start,A,b,A,b,A,end"));
+    let shortest: Vec<String> = caves.shortest_paths().take(2).collect();
+    assert_eq!(shortest, vec!["start,A,end", "start,b,end"]);
+  }
+
+  #[test]
+  fn keeps_out_of_forbidden_caves() {
+    let rules = Rules {
+      forbidden: HashSet::from([String::from("A")]),
+      ..Rules::part_1()
+    };
+    assert_eq!(count(&rules), 1);
+  }
+
+  #[test]
+  fn visits_caves_up_to_their_budget() {
+    let rules = Rules {
+      budgets: HashMap::from([(String::from("b"), 2)]),
+      ..Rules::part_1()
+    };
+    assert_eq!(count(&rules), 30);
+  }
+
+  #[test]
+  fn sets_the_rules_from_explore_parameters() {
+    let (_, edges) = Day12::parse(EXAMPLE).unwrap();
+    let params = Day12::params(&edges);
+    let mut values: Vec<usize> = params.iter().map(|p| p.part_1).collect();
+    let set = |values: &mut Vec<usize>, name: &str, value: usize| {
+      values[params.iter().position(|p| p.name == name).unwrap()] = value;
+    };
+    set(&mut values, "budget_b", 2);
+    assert_eq!(Day12::run_with(&edges, &values), Ok(String::from("30")));
+    set(&mut values, "forbid_A", 1);
+    set(&mut values, "budget_b", 1);
+    assert_eq!(Day12::run_with(&edges, &values), Ok(String::from("1")));
   }
 }
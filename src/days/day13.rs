@@ -1,11 +1,15 @@
+use crate::parser::{blank_line, lines, print_list};
 use nom::branch::alt;
 use nom::bytes::complete::tag;
-use nom::character::complete::{newline, u32};
-use nom::multi::{many1, separated_list0};
-use nom::sequence::tuple;
+use nom::character::complete::u32;
+use nom::sequence::{separated_pair, tuple};
 use nom::IResult;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::HashSet;
+use std::fmt::{self, Display, Formatter};
 
-use crate::days::Day;
+use crate::days::{Day, Explore, Frame, Generate, NoSolution, Print, Visualize};
 
 pub struct Day13;
 
@@ -26,12 +30,21 @@ fn parse_fold(input: &str) -> IResult<&str, FoldAlong> {
   Ok((cont, res))
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum FoldAlong {
   X(u32),
   Y(u32),
 }
 
+impl Display for FoldAlong {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match self {
+      FoldAlong::X(i) => write!(f, "fold along x={}", i),
+      FoldAlong::Y(i) => write!(f, "fold along y={}", i),
+    }
+  }
+}
+
 fn run(points: &[(u32, u32)], folds: &[FoldAlong]) -> Vec<(u32, u32)> {
   let mut dots: Vec<(u32, u32)> = points.to_vec();
   for f in folds {
@@ -63,27 +76,41 @@ fn run(points: &[(u32, u32)], folds: &[FoldAlong]) -> Vec<(u32, u32)> {
   dots
 }
 
+fn to_frame(dots: &[(u32, u32)]) -> Frame {
+  let width = dots.iter().map(|(x, _)| *x).max().unwrap_or(0) + 1;
+  let height = dots.iter().map(|(_, y)| *y).max().unwrap_or(0) + 1;
+  let mut res = vec![vec![('░', None); width as usize]; height as usize];
+  for (x, y) in dots {
+    res[*y as usize][*x as usize] = ('█', None);
+  }
+  res
+}
+
 impl Day for Day13 {
   type Input = (Vec<(u32, u32)>, Vec<FoldAlong>);
 
   fn parse(input: &str) -> IResult<&str, Self::Input> {
-    let (cont, points) = separated_list0(newline, parse_dot)(input)?;
-    let (cont, (_, folds)) = tuple((many1(newline), separated_list0(newline, parse_fold)))(cont)?;
-    Ok((cont, (points, folds)))
+    separated_pair(lines(parse_dot), blank_line, lines(parse_fold))(input)
   }
 
   type Output1 = usize;
 
-  fn part_1((points, folds): &Self::Input) -> Self::Output1 {
-    let dots = run(points, &[folds[0]]);
+  fn part_1((points, folds): &Self::Input) -> Result<Self::Output1, NoSolution> {
+    let first_fold = folds
+      .first()
+      .ok_or_else(|| NoSolution(String::from("there are no folds")))?;
+    let dots = run(points, &[*first_fold]);
 
-    dots.len()
+    Ok(dots.len())
   }
 
   type Output2 = String;
 
-  fn part_2((points, folds): &Self::Input) -> Self::Output2 {
+  fn part_2((points, folds): &Self::Input) -> Result<Self::Output2, NoSolution> {
     let dots = run(points, folds);
+    if dots.is_empty() {
+      return Err(NoSolution(String::from("there are no dots")));
+    }
     let xs = dots.iter().map(|(x, _)| x);
     let max_x = xs.clone().max().unwrap();
     let min_x = xs.min().unwrap();
@@ -103,6 +130,79 @@ impl Day for Day13 {
         }
       }
     }
-    res
+    Ok(res)
+  }
+}
+
+impl Visualize for Day13 {
+  // The paper before folding, and after every fold
+  fn frames((points, folds): &Self::Input) -> Box<dyn Iterator<Item = Frame> + '_> {
+    Box::new((0..=folds.len()).map(|i| to_frame(&run(points, &folds[..i]))))
+  }
+}
+
+impl Generate for Day13 {
+  fn generate<R: Rng>(rng: &mut R, scale: usize) -> String {
+    // Build the folds backwards from the final 40x6 code, so every fold is in the exact middle
+    let mut x_folds = vec![];
+    let mut width = 40;
+    for _ in 0..5 {
+      x_folds.push(width);
+      width = width * 2 + 1;
+    }
+    let mut y_folds = vec![];
+    let mut height = 6;
+    for _ in 0..7 {
+      y_folds.push(height);
+      height = height * 2 + 1;
+    }
+
+    let mut folds = vec![];
+    while !x_folds.is_empty() || !y_folds.is_empty() {
+      if y_folds.is_empty() || (!x_folds.is_empty() && rng.gen_bool(0.5)) {
+        folds.push(FoldAlong::X(x_folds.pop().unwrap()));
+      } else {
+        folds.push(FoldAlong::Y(y_folds.pop().unwrap()));
+      }
+    }
+
+    // Pick the dots of the final code, and unfold copies of them onto random sides of each fold
+    let code: Vec<(u32, u32)> = (0..6)
+      .flat_map(|y| (0..40).map(move |x| (x, y)))
+      .filter(|_| rng.gen_bool(0.4))
+      .collect();
+    let mut dots = HashSet::new();
+    while dots.len() < 800 * scale {
+      let (mut x, mut y) = code[rng.gen_range(0..code.len())];
+      for f in folds.iter().rev() {
+        if rng.gen_bool(0.5) {
+          match f {
+            FoldAlong::X(i) => x = 2 * i - x,
+            FoldAlong::Y(i) => y = 2 * i - y,
+          }
+        }
+      }
+      dots.insert((x, y));
+    }
+    let mut dots: Vec<(u32, u32)> = dots.into_iter().collect();
+    dots.sort_unstable();
+    dots.shuffle(rng);
+
+    let dots: Vec<String> = dots.iter().map(|(x, y)| format!("{},{}", x, y)).collect();
+    format!("{}\n\n{}", dots.join("\n"), print_list(&folds, "\n"))
   }
 }
+
+impl Print for Day13 {
+  fn print(input: &Self::Input) -> String {
+    let (dots, folds) = input;
+    let dots = dots
+      .iter()
+      .map(|(x, y)| format!("{},{}", x, y))
+      .collect::<Vec<String>>()
+      .join("\n");
+    format!("{}\n\n{}", dots, print_list(folds, "\n"))
+  }
+}
+
+impl Explore for Day13 {}
@@ -1,11 +1,15 @@
+use crate::parser::{blank_line, lines};
 use nom::bytes::complete::tag;
-use nom::character::complete::{alpha1, anychar, newline};
-use nom::multi::{many1, separated_list0};
-use nom::sequence::tuple;
+use nom::character::complete::{alpha1, anychar};
+use nom::sequence::{separated_pair, tuple};
 use nom::IResult;
+use rand::seq::index::sample;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::borrow::Cow;
 use std::collections::HashMap;
 
-use crate::days::Day;
+use crate::days::{Day, Explore, Generate, NoSolution, Param, Print};
 
 pub struct Day14;
 
@@ -75,20 +79,64 @@ impl Day for Day14 {
   type Input = (String, Vec<Rule>);
 
   fn parse(input: &str) -> IResult<&str, Self::Input> {
-    let (cont, (init, _)) = tuple((alpha1, many1(newline)))(input)?;
-    let (cont, l) = separated_list0(newline, parse_rule)(cont)?;
-    Ok((cont, (init.to_owned(), l)))
+    let (cont, (init, rules)) = separated_pair(alpha1, blank_line, lines(parse_rule))(input)?;
+    Ok((cont, (init.to_owned(), rules)))
   }
 
   type Output1 = usize;
 
-  fn part_1((init, rules): &Self::Input) -> Self::Output1 {
-    run(10, init, rules)
+  fn part_1((init, rules): &Self::Input) -> Result<Self::Output1, NoSolution> {
+    Ok(run(10, init, rules))
   }
 
   type Output2 = usize;
 
-  fn part_2((init, rules): &Self::Input) -> Self::Output2 {
-    run(40, init, rules)
+  fn part_2((init, rules): &Self::Input) -> Result<Self::Output2, NoSolution> {
+    Ok(run(40, init, rules))
+  }
+}
+
+impl Generate for Day14 {
+  fn generate<R: Rng>(rng: &mut R, scale: usize) -> String {
+    let letters: Vec<char> = sample(rng, 26, 10)
+      .iter()
+      .map(|i| (b'A' + i as u8) as char)
+      .collect();
+    let template: String = (0..20 * scale)
+      .map(|_| letters[rng.gen_range(0..letters.len())])
+      .collect();
+    let mut rules = vec![];
+    for l1 in &letters {
+      for l2 in &letters {
+        let r = letters[rng.gen_range(0..letters.len())];
+        rules.push(format!("{}{} -> {}", l1, l2, r));
+      }
+    }
+    rules.shuffle(rng);
+    format!("{}\n\n{}", template, rules.join("\n"))
+  }
+}
+
+impl Print for Day14 {
+  fn print(input: &Self::Input) -> String {
+    let (template, rules) = input;
+    let rules = rules
+      .iter()
+      .map(|((l1, l2), r)| format!("{}{} -> {}", l1, l2, r))
+      .collect::<Vec<String>>()
+      .join("\n");
+    format!("{}\n\n{}", template, rules)
+  }
+}
+
+impl Explore for Day14 {
+  const PARAMS: &'static [Param] = &[Param {
+    name: Cow::Borrowed("steps"),
+    part_1: 10,
+    part_2: 40,
+  }];
+
+  fn run_with((init, rules): &Self::Input, values: &[usize]) -> Result<String, NoSolution> {
+    Ok(run(values[0], init, rules).to_string())
   }
 }
@@ -1,13 +1,40 @@
-use crate::parser::digit_grid;
+use crate::parser::{digit_grid, Grid};
 use nom::IResult;
 use pathfinding::directed::astar::astar;
+use rand::Rng;
+use std::borrow::Cow;
 
-use crate::days::Day;
+use crate::days::{Day, Explore, Generate, NoSolution, Param, Print};
 
 pub struct Day15;
 
+// The lowest total risk of a path through the map repeated `tiles` times in both directions, where
+// every repetition to the right or down has risk levels one higher, wrapping back around to 1
+fn lowest_risk(input: &Grid<u32>, tiles: usize) -> Result<u32, NoSolution> {
+  if input.is_empty() || tiles == 0 {
+    return Err(NoSolution(String::from("the map is empty")));
+  }
+  let (width, height) = (input.width(), input.height());
+  let map = Grid::from_fn(width * tiles, height * tiles, |(x, y)| {
+    let risk = input[(x % width, y % height)] - 1 + (x / width) as u32 + (y / height) as u32;
+    risk % 9 + 1
+  });
+  let end = (map.width() - 1, map.height() - 1);
+
+  let res = astar(
+    &(0, 0),
+    |p| map.neighbors4(*p).map(|n| (n, map[n])),
+    |(x, y)| (end.0 - x + end.1 - y) as u32,
+    |p| *p == end,
+  );
+
+  res
+    .map(|(_, risk)| risk)
+    .ok_or_else(|| NoSolution(String::from("there is no path")))
+}
+
 impl Day for Day15 {
-  type Input = Vec<Vec<u32>>;
+  type Input = Grid<u32>;
 
   fn parse(input: &str) -> IResult<&str, Self::Input> {
     digit_grid(input)
@@ -15,71 +42,41 @@ impl Day for Day15 {
 
   type Output1 = u32;
 
-  fn part_1(input: &Self::Input) -> Self::Output1 {
-    let height = input.len();
-    let width = input[0].len();
+  fn part_1(input: &Self::Input) -> Result<Self::Output1, NoSolution> {
+    lowest_risk(input, 1)
+  }
 
-    let res = astar(
-      &(0, 0),
-      |(x, y)| {
-        let mut res: Vec<((usize, usize), u32)> = vec![];
-        if *x > 0 {
-          res.push(((*x - 1, *y), input[*y][*x - 1]));
-        }
-        if *x < width - 1 {
-          res.push(((*x + 1, *y), input[*y][*x + 1]));
-        }
-        if *y > 0 {
-          res.push(((*x, *y - 1), input[*y - 1][*x]));
-        }
-        if *y < height - 1 {
-          res.push(((*x, *y + 1), input[*y + 1][*x]));
-        }
-        res
-      },
-      |(x, y)| (width - x - 1 + height - y - 1) as u32,
-      |(x, y)| *x == width - 1 && *y == height - 1,
-    );
+  type Output2 = u32;
 
-    res.unwrap().1
+  fn part_2(input: &Self::Input) -> Result<Self::Output2, NoSolution> {
+    lowest_risk(input, 5)
   }
+}
 
-  type Output2 = u32;
+impl Generate for Day15 {
+  fn generate<R: Rng>(rng: &mut R, scale: usize) -> String {
+    let size = 100 * scale;
+    (0..size)
+      .map(|_| (0..size).map(|_| rng.gen_range('1'..='9')).collect::<String>())
+      .collect::<Vec<String>>()
+      .join("\n")
+  }
+}
 
-  fn part_2(input: &Self::Input) -> Self::Output2 {
-    let height = input.len();
-    let width = input[0].len();
+impl Print for Day15 {
+  fn print(input: &Self::Input) -> String {
+    input.to_string()
+  }
+}
 
-    let res = astar(
-      &(0, 0),
-      |(x, y)| {
-        let mut poss: Vec<(usize, usize)> = vec![];
-        if *x > 0 {
-          poss.push((*x - 1, *y));
-        }
-        if *x < width * 5 - 1 {
-          poss.push((*x + 1, *y));
-        }
-        if *y > 0 {
-          poss.push((*x, *y - 1));
-        }
-        if *y < height * 5 - 1 {
-          poss.push((*x, *y + 1));
-        }
-        let res: Vec<((usize, usize), u32)> = poss
-          .iter()
-          .map(|p| {
-            let (x, y) = p;
-            let cost = input[y % height][x % width] - 1 + (x / width) as u32 + (y / height) as u32;
-            (*p, cost % 9 + 1)
-          })
-          .collect();
-        res
-      },
-      |(x, y)| (width * 5 - x - 1 + height * 5 - y - 1) as u32,
-      |(x, y)| *x == width * 5 - 1 && *y == height * 5 - 1,
-    );
+impl Explore for Day15 {
+  const PARAMS: &'static [Param] = &[Param {
+    name: Cow::Borrowed("tiles"),
+    part_1: 1,
+    part_2: 5,
+  }];
 
-    res.unwrap().1
+  fn run_with(input: &Self::Input, values: &[usize]) -> Result<String, NoSolution> {
+    lowest_risk(input, values[0]).map(|risk| risk.to_string())
   }
 }
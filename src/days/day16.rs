@@ -1,14 +1,14 @@
-use nom::bits::complete as bits;
-use nom::branch::alt;
-use nom::combinator::eof;
-use nom::combinator::map as pmap;
-use nom::multi::many0;
-use nom::multi::many_m_n;
+use crate::bigint::BigUint;
+use nom::character::complete::hex_digit1;
+use nom::error::{Error, ErrorKind};
 use nom::IResult;
+use rand::Rng;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, BufReader, Read};
 
-use crate::days::Day;
+use crate::days::{Day, Explore, Generate, NoSolution, Print};
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Operator {
   Sum,
   Product,
@@ -19,11 +19,11 @@ pub enum Operator {
   EqualTo,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum Packet {
   Literal {
     version: u8,
-    value: u64,
+    value: BigUint,
   },
   OperatorPacket {
     version: u8,
@@ -35,117 +35,6 @@ pub enum Packet {
 use Operator::*;
 use Packet::*;
 
-fn parse_transmission(input: (&[u8], usize)) -> IResult<(&[u8], usize), Packet> {
-  let (cont, packet) = parse_packet(input)?;
-  // Consume optional zeroes at the end of input
-  let (cont, _) = many0(bits::tag(0, 1usize))(cont)?;
-  Ok((cont, packet))
-}
-
-fn parse_packet(input: (&[u8], usize)) -> IResult<(&[u8], usize), Packet> {
-  // Take version
-  let (cont, version) = bits::take(3usize)(input)?;
-  // Parse either a literal value or an operator packet
-  alt((parse_literal(version), parse_operator_packet(version)))(cont)
-}
-
-fn parse_literal(version: u8) -> impl FnMut((&[u8], usize)) -> IResult<(&[u8], usize), Packet> {
-  move |input| {
-    // A literal has type Id 4
-    let (cont, _) = bits::tag(4, 3usize)(input)?;
-    // Parse many 5-bit blocks starting with a 1
-    let (cont, mut blocks) = many0(parse_literal_block)(cont)?;
-    // Parse the final 5-bit block starting with a 0
-    let (cont, _) = bits::tag(0, 1usize)(cont)?;
-    let (cont, last_block) = bits::take(4usize)(cont)?;
-
-    blocks.push(last_block);
-    // Fold the blocks to a u64 value
-    let res = Literal {
-      version,
-      value: blocks.iter().fold(0, |acc, x| (acc << 4) + *x as u64),
-    };
-
-    Ok((cont, res))
-  }
-}
-
-fn parse_literal_block(input: (&[u8], usize)) -> IResult<(&[u8], usize), u8> {
-  // Only continue when the first bit is a 1, return the 4 bits after
-  let (cont, _) = bits::tag(1, 1usize)(input)?;
-  bits::take(4usize)(cont)
-}
-
-fn parse_operator_packet(
-  version: u8,
-) -> impl FnMut((&[u8], usize)) -> IResult<(&[u8], usize), Packet> {
-  move |input| {
-    // Get the operator and continue
-    let (cont, operator) = parse_operator(input)?;
-    alt((
-      parse_length_operator(version, operator),
-      parse_count_operator(version, operator),
-    ))(cont)
-  }
-}
-
-fn parse_operator(input: (&[u8], usize)) -> IResult<(&[u8], usize), Operator> {
-  alt((
-    pmap(bits::tag(0, 3usize), |_| Sum),
-    pmap(bits::tag(1, 3usize), |_| Product),
-    pmap(bits::tag(2, 3usize), |_| Min),
-    pmap(bits::tag(3, 3usize), |_| Max),
-    pmap(bits::tag(5, 3usize), |_| GreaterThan),
-    pmap(bits::tag(6, 3usize), |_| LessThan),
-    pmap(bits::tag(7, 3usize), |_| EqualTo),
-  ))(input)
-}
-
-fn parse_length_operator(
-  version: u8,
-  operator: Operator,
-) -> impl FnMut((&[u8], usize)) -> IResult<(&[u8], usize), Packet> {
-  move |input| {
-    // Length operators have a 0 after the operator type
-    let (cont, _) = bits::tag(0, 1usize)(input)?;
-    // The length is specified in the next 15 bits
-    let (cont, length) = bits::take(15usize)(cont)?;
-    // Parse sub-packages in the next X bits according to the length we just parsed
-    // Only succeeds if the entire length is used
-    let (cont, sub_packets) = parse_with_length(length, many0(parse_packet))(cont)?;
-    Ok((
-      cont,
-      OperatorPacket {
-        version,
-        operator,
-        sub_packets,
-      },
-    ))
-  }
-}
-
-fn parse_count_operator(
-  version: u8,
-  operator: Operator,
-) -> impl FnMut((&[u8], usize)) -> IResult<(&[u8], usize), Packet> {
-  move |input| {
-    // Count operators have a 1 after the operator type
-    let (cont, _) = bits::tag(1, 1usize)(input)?;
-    // The next 11 bits represent the number of sub_packages
-    let (cont, count) = bits::take(11usize)(cont)?;
-    // Parse exactly that many sub-packages
-    let (cont, sub_packets) = many_m_n(count, count, parse_packet)(cont)?;
-    Ok((
-      cont,
-      OperatorPacket {
-        version,
-        operator,
-        sub_packets,
-      },
-    ))
-  }
-}
-
 fn sum_versions(packet: &Packet) -> usize {
   match packet {
     Literal { version: v, .. } => *v as usize,
@@ -157,111 +46,742 @@ fn sum_versions(packet: &Packet) -> usize {
   }
 }
 
-fn eval(packet: &Packet) -> u64 {
+fn eval(packet: &Packet) -> Result<BigUint, NoSolution> {
+  let (operator, sub_packets) = match packet {
+    Literal { value, .. } => return Ok(value.clone()),
+    OperatorPacket {
+      operator,
+      sub_packets,
+      ..
+    } => (operator, sub_packets),
+  };
+  let values = sub_packets.iter().map(eval).collect::<Result<Vec<BigUint>, _>>()?;
+  let name = operator_name(*operator);
+  let res = match (operator, &values[..]) {
+    (Sum, _) => values.iter().fold(BigUint::zero(), |acc, v| &acc + v),
+    (Product, _) => values.iter().fold(BigUint::from(1), |acc, v| &acc * v),
+    (Min | Max, []) => return Err(NoSolution(format!("a {} packet has no operands", name))),
+    (Min, _) => values.iter().min().unwrap().clone(),
+    (Max, _) => values.iter().max().unwrap().clone(),
+    (GreaterThan, [a, b]) => BigUint::from((a > b) as u64),
+    (LessThan, [a, b]) => BigUint::from((a < b) as u64),
+    (EqualTo, [a, b]) => BigUint::from((a == b) as u64),
+    (GreaterThan | LessThan | EqualTo, _) => {
+      return Err(NoSolution(format!(
+        "a {} packet has {} operands instead of 2",
+        name,
+        values.len()
+      )))
+    }
+  };
+  Ok(res)
+}
+
+pub struct Day16;
+
+fn push_bits(bits: &mut Vec<bool>, value: u64, length: usize) {
+  for i in (0..length).rev() {
+    bits.push(value >> i & 1 == 1);
+  }
+}
+
+fn push_literal(bits: &mut Vec<bool>, value: &BigUint) {
+  let nibbles = value.nibbles();
+  for (i, nibble) in nibbles.iter().enumerate() {
+    push_bits(bits, if i + 1 < nibbles.len() { 1 } else { 0 }, 1);
+    push_bits(bits, *nibble as u64, 4);
+  }
+}
+
+// Pads the bits with zeroes up to a whole number of bytes, and writes them as hex
+fn bits_to_hex(mut bits: Vec<bool>) -> String {
+  bits.resize(bits.len().div_ceil(8) * 8, false);
+  bits
+    .chunks(4)
+    .map(|c| {
+      let nibble = c.iter().fold(0, |acc, b| acc * 2 + *b as u32);
+      char::from_digit(nibble, 16).unwrap().to_ascii_uppercase()
+    })
+    .collect()
+}
+
+fn type_id(operator: Operator) -> u64 {
+  match operator {
+    Sum => 0,
+    Product => 1,
+    Min => 2,
+    Max => 3,
+    GreaterThan => 5,
+    LessThan => 6,
+    EqualTo => 7,
+  }
+}
+
+// How an operator packet gives the size of its sub-packets: as their total length in bits with
+// length type 0, or as their number with length type 1
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LengthType {
+  Bits,
+  Count,
+}
+
+use LengthType::*;
+
+// Why a packet can not be encoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodeErr {
+  // Versions have 3 bits
+  Version(u8),
+  // Length type 0 has 15 bits for the length of the sub-packets
+  TooLong(usize),
+  // Length type 1 has 11 bits for the number of sub-packets
+  TooMany(usize),
+}
+
+impl Display for EncodeErr {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match self {
+      EncodeErr::Version(v) => write!(f, "version {} does not fit in 3 bits", v),
+      EncodeErr::TooLong(n) => write!(f, "{} bits of sub-packets do not fit in 15 bits", n),
+      EncodeErr::TooMany(n) => write!(f, "{} sub-packets do not fit in 11 bits", n),
+    }
+  }
+}
+
+fn push_length(
+  bits: &mut Vec<bool>,
+  length_type: LengthType,
+  sub_bits: usize,
+  count: usize,
+) -> Result<(), EncodeErr> {
+  match length_type {
+    Bits if sub_bits >= 1 << 15 => Err(EncodeErr::TooLong(sub_bits)),
+    Bits => {
+      push_bits(bits, 0, 1);
+      push_bits(bits, sub_bits as u64, 15);
+      Ok(())
+    }
+    Count if count >= 1 << 11 => Err(EncodeErr::TooMany(count)),
+    Count => {
+      push_bits(bits, 1, 1);
+      push_bits(bits, count as u64, 11);
+      Ok(())
+    }
+  }
+}
+
+// Appends the bits of a packet. Every operator packet gets the length type `choose` returns for
+// it, or if that is None, length type 0 unless the sub-packets are too long for it.
+pub fn encode_with<F>(packet: &Packet, bits: &mut Vec<bool>, choose: &mut F) -> Result<(), EncodeErr>
+where
+  F: FnMut(&Packet) -> Option<LengthType>,
+{
   match packet {
-    Literal { value, .. } => *value as u64,
+    Literal { version, value } => {
+      if *version >= 8 {
+        return Err(EncodeErr::Version(*version));
+      }
+      push_bits(bits, *version as u64, 3);
+      push_bits(bits, 4, 3);
+      push_literal(bits, value);
+    }
     OperatorPacket {
+      version,
+      operator,
       sub_packets,
+    } => {
+      if *version >= 8 {
+        return Err(EncodeErr::Version(*version));
+      }
+      push_bits(bits, *version as u64, 3);
+      push_bits(bits, type_id(*operator), 3);
+      let requested = choose(packet);
+      let mut sub_bits = vec![];
+      for p in sub_packets {
+        encode_with(p, &mut sub_bits, choose)?;
+      }
+      let (length, count) = (sub_bits.len(), sub_packets.len());
+      match requested {
+        Some(length_type) => push_length(bits, length_type, length, count)?,
+        None => push_length(bits, Bits, length, count)
+          .or_else(|_| push_length(bits, Count, length, count))?,
+      }
+      bits.extend(sub_bits);
+    }
+  }
+  Ok(())
+}
+
+// The transmission of a packet in hex, padded with zeroes to whole bytes. All operator packets use
+// the given length type, or the one `encode_with` chooses if it is None.
+pub fn to_hex(packet: &Packet, length_type: Option<LengthType>) -> Result<String, EncodeErr> {
+  let mut bits = vec![];
+  encode_with(packet, &mut bits, &mut |_| length_type)?;
+  Ok(bits_to_hex(bits))
+}
+
+// The packet as an infix expression. `to_hex` gives its transmission.
+impl Display for Packet {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    write!(f, "{}", infix(self))
+  }
+}
+
+fn generate_literal<R: Rng>(rng: &mut R, max_value: u64) -> Packet {
+  // Now and then a literal longer than 16 hexadecimal digits, which does not fit in a u64
+  let value = if rng.gen_bool(0.05) {
+    let nibbles: Vec<u8> = (0..rng.gen_range(17..=24)).map(|_| rng.gen_range(0..16)).collect();
+    BigUint::from_nibbles(&nibbles)
+  } else {
+    BigUint::from(rng.gen_range(0..=max_value))
+  };
+  Literal {
+    version: rng.gen_range(0..8),
+    value,
+  }
+}
+
+fn generate_packet<R: Rng>(rng: &mut R, depth: usize) -> Packet {
+  if depth == 0 || rng.gen_bool(0.2) {
+    return generate_literal(rng, 0xFFF);
+  }
+  let version = rng.gen_range(0..8);
+  let operator = [Sum, Product, Min, Max, GreaterThan, LessThan, EqualTo][rng.gen_range(0..7)];
+  let count = match operator {
+    GreaterThan | LessThan | EqualTo => 2,
+    _ => rng.gen_range(1..=3),
+  };
+  let sub_packets = (0..count).map(|_| generate_packet(rng, depth - 1)).collect();
+  OperatorPacket {
+    version,
+    operator,
+    sub_packets,
+  }
+}
+
+fn operator_name(operator: Operator) -> &'static str {
+  match operator {
+    Sum => "sum",
+    Product => "product",
+    Min => "min",
+    Max => "max",
+    GreaterThan => "greater than",
+    LessThan => "less than",
+    EqualTo => "equal to",
+  }
+}
+
+fn operator_from_id(id: u64) -> Option<Operator> {
+  [Sum, Product, Min, Max, GreaterThan, LessThan, EqualTo]
+    .into_iter()
+    .find(|o| type_id(*o) == id)
+}
+
+// Why a transmission can not be decoded. Every error has the bit it happened at.
+#[derive(Debug)]
+pub enum DecodeErr {
+  // Reading `wanted` bits at `pos` runs past the end of the transmission at bit `len`
+  End { pos: usize, wanted: usize, len: usize },
+  // Reading `wanted` bits at `pos` runs past the sub-packets of a packet, which end at bit `end`
+  Overrun { pos: usize, wanted: usize, end: usize },
+  // A character of the transmission that is not a hexadecimal digit
+  NotHex { pos: usize, found: char },
+  // The stream failed
+  Io { pos: usize, err: io::Error },
+}
+
+impl DecodeErr {
+  pub fn pos(&self) -> usize {
+    match self {
+      DecodeErr::End { pos, .. }
+      | DecodeErr::Overrun { pos, .. }
+      | DecodeErr::NotHex { pos, .. }
+      | DecodeErr::Io { pos, .. } => *pos,
+    }
+  }
+}
+
+impl Display for DecodeErr {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match self {
+      DecodeErr::End { pos, wanted, len } => write!(
+        f,
+        "reading {} bits at bit {} runs past the end of the transmission at bit {}",
+        wanted, pos, len
+      ),
+      DecodeErr::Overrun { pos, wanted, end } => write!(
+        f,
+        "reading {} bits at bit {} runs past the end of the sub-packets at bit {}",
+        wanted, pos, end
+      ),
+      DecodeErr::NotHex { pos, found } => {
+        write!(f, "{:?} at bit {} is not a hexadecimal digit", found, pos)
+      }
+      DecodeErr::Io { pos, err } => write!(f, "the stream failed at bit {}: {}", pos, err),
+    }
+  }
+}
+
+// Reads the bits of a transmission in hex from a stream, one hexadecimal digit at a time. The
+// transmission ends at the end of the stream or at the first whitespace. The stream is buffered
+// here, so it does not have to be.
+pub struct BitReader<R> {
+  bytes: io::Bytes<BufReader<R>>,
+  // Bits taken from the stream but not read yet, in the lowest `buffered` bits
+  buffer: u64,
+  buffered: usize,
+  ended: bool,
+  pos: usize,
+  // Where the sub-reader `limited` runs ends, if there is one
+  end: Option<usize>,
+}
+
+impl<R: Read> BitReader<R> {
+  pub fn new(source: R) -> BitReader<R> {
+    BitReader {
+      bytes: BufReader::new(source).bytes(),
+      buffer: 0,
+      buffered: 0,
+      ended: false,
+      pos: 0,
+      end: None,
+    }
+  }
+
+  // The number of bits read so far
+  pub fn pos(&self) -> usize {
+    self.pos
+  }
+
+  // The bits left in the current sub-reader, or None outside of one
+  pub fn remaining(&self) -> Option<usize> {
+    self.end.map(|end| end - self.pos)
+  }
+
+  // Takes the next hexadecimal digit from the stream, and returns false if the transmission ended
+  fn fill(&mut self) -> Result<bool, DecodeErr> {
+    if self.ended {
+      return Ok(false);
+    }
+    let pos = self.pos + self.buffered;
+    let byte = match self.bytes.next() {
+      None => None,
+      Some(Err(err)) => return Err(DecodeErr::Io { pos, err }),
+      Some(Ok(b)) if b.is_ascii_whitespace() => None,
+      Some(Ok(b)) => Some(b),
+    };
+    let Some(byte) = byte else {
+      self.ended = true;
+      return Ok(false);
+    };
+    let found = byte as char;
+    let digit = found.to_digit(16).ok_or(DecodeErr::NotHex { pos, found })?;
+    self.buffer = self.buffer << 4 | digit as u64;
+    self.buffered += 4;
+    Ok(true)
+  }
+
+  // The next n bits as a number, the first bit the most significant. Packets never need more than
+  // 32 bits at once, which keeps them within the buffer.
+  fn read(&mut self, n: usize) -> Result<u64, DecodeErr> {
+    assert!(n <= 32, "can not read {} bits at once", n);
+    if let Some(end) = self.end {
+      if self.pos + n > end {
+        return Err(DecodeErr::Overrun {
+          pos: self.pos,
+          wanted: n,
+          end,
+        });
+      }
+    }
+    while self.buffered < n {
+      if !self.fill()? {
+        return Err(DecodeErr::End {
+          pos: self.pos,
+          wanted: n,
+          len: self.pos + self.buffered,
+        });
+      }
+    }
+    self.buffered -= n;
+    let res = self.buffer >> self.buffered;
+    self.buffer &= (1 << self.buffered) - 1;
+    self.pos += n;
+    Ok(res)
+  }
+
+  // Runs `f` on a sub-reader of the next n bits, which can not read past them. The sub-reader
+  // must be read to its end.
+  pub fn limited<T, F>(&mut self, n: usize, f: F) -> Result<T, DecodeErr>
+  where
+    F: FnOnce(&mut BitReader<R>) -> Result<T, DecodeErr>,
+  {
+    let end = self.pos + n;
+    if let Some(outer) = self.end {
+      if end > outer {
+        return Err(DecodeErr::Overrun {
+          pos: self.pos,
+          wanted: n,
+          end: outer,
+        });
+      }
+    }
+    let outer = self.end.replace(end);
+    let res = f(self);
+    self.end = outer;
+    let res = res?;
+    if self.pos < end {
+      return Err(DecodeErr::Overrun {
+        pos: self.pos,
+        wanted: 0,
+        end,
+      });
+    }
+    Ok(res)
+  }
+}
+
+fn decode_literal<R: Read>(reader: &mut BitReader<R>) -> Result<BigUint, DecodeErr> {
+  // 5-bit blocks of a hexadecimal digit, each starting with a 1 if another block follows
+  let mut nibbles = vec![];
+  loop {
+    let more = reader.read(1)?;
+    nibbles.push(reader.read(4)? as u8);
+    if more == 0 {
+      return Ok(BigUint::from_nibbles(&nibbles));
+    }
+  }
+}
+
+fn decode_packet<R: Read>(reader: &mut BitReader<R>) -> Result<Packet, DecodeErr> {
+  let version = reader.read(3)? as u8;
+  let Some(operator) = operator_from_id(reader.read(3)?) else {
+    // Type ID 4 is the only one that is not an operator
+    let value = decode_literal(reader)?;
+    return Ok(Literal { version, value });
+  };
+  let sub_packets = if reader.read(1)? == 0 {
+    // Length type 0: the sub-packets take up exactly the next X bits
+    let length = reader.read(15)? as usize;
+    reader.limited(length, |sub_reader| {
+      let mut res = vec![];
+      while sub_reader.remaining() != Some(0) {
+        res.push(decode_packet(sub_reader)?);
+      }
+      Ok(res)
+    })?
+  } else {
+    // Length type 1: the number of sub-packets
+    let count = reader.read(11)?;
+    (0..count)
+      .map(|_| decode_packet(reader))
+      .collect::<Result<_, _>>()?
+  };
+  Ok(OperatorPacket {
+    version,
+    operator,
+    sub_packets,
+  })
+}
+
+// Decodes the outermost packet of a transmission in hex. The stream is read ahead in blocks, so
+// whatever follows the packet, like the zeroes that pad it to whole bytes, is read and dropped.
+pub fn decode<R: Read>(source: R) -> Result<Packet, DecodeErr> {
+  decode_packet(&mut BitReader::new(source))
+}
+
+// Appends a line for the next packet of the reader and every packet inside it
+fn disasm_packet<R: Read>(
+  reader: &mut BitReader<R>,
+  depth: usize,
+  lines: &mut Vec<String>,
+) -> Result<(), DecodeErr> {
+  let offset = reader.pos();
+  let version = reader.read(3)?;
+  let id = reader.read(3)?;
+  let head = format!("{:>6}  {}v{} type {}", offset, "  ".repeat(depth), version, id);
+  let Some(operator) = operator_from_id(id) else {
+    let value = decode_literal(reader)?;
+    lines.push(format!("{} (literal) {}", head, value));
+    return Ok(());
+  };
+  let name = operator_name(operator);
+  if reader.read(1)? == 0 {
+    let length = reader.read(15)? as usize;
+    lines.push(format!("{} ({}), length type 0: {} bits", head, name, length));
+    reader.limited(length, |sub_reader| {
+      while sub_reader.remaining() != Some(0) {
+        disasm_packet(sub_reader, depth + 1, lines)?;
+      }
+      Ok(())
+    })?;
+  } else {
+    let count = reader.read(11)?;
+    let plural = if count == 1 { "" } else { "s" };
+    lines.push(format!("{} ({}), length type 1: {} sub-packet{}", head, name, count, plural));
+    for _ in 0..count {
+      disasm_packet(reader, depth + 1, lines)?;
+    }
+  }
+  Ok(())
+}
+
+// Lists the packets of a transmission with the bit offset they start at, and the expression it
+// evaluates
+pub fn disasm(hex: &str) -> Result<String, DecodeErr> {
+  let mut lines = vec![String::from("offset  packet")];
+  disasm_packet(&mut BitReader::new(hex.trim().as_bytes()), 0, &mut lines)?;
+  let packet = decode(hex.trim().as_bytes())?;
+  lines.push(String::new());
+  match eval(&packet) {
+    Ok(value) => lines.push(format!("{} = {}", infix(&packet), value)),
+    Err(e) => lines.push(format!("{} can not be evaluated: {}", infix(&packet), e)),
+  }
+  Ok(lines.join("\n"))
+}
+
+// How tightly a packet binds in infix notation. Comparisons bind the loosest, and are written in
+// parentheses inside other comparisons. Operators with a single operand are written as just that
+// operand.
+fn precedence(packet: &Packet) -> u8 {
+  match packet {
+    OperatorPacket {
+      operator: Min | Max,
+      ..
+    } => 4,
+    OperatorPacket { sub_packets, .. } if sub_packets.len() == 1 => precedence(&sub_packets[0]),
+    OperatorPacket { sub_packets, .. } if sub_packets.is_empty() => 4,
+    OperatorPacket { operator, .. } => match operator {
+      GreaterThan | LessThan | EqualTo => 1,
+      Sum => 2,
+      _ => 3,
+    },
+    Literal { .. } => 4,
+  }
+}
+
+fn infix_with(packet: &Packet, min_precedence: u8) -> String {
+  let res = match packet {
+    Literal { value, .. } => value.to_string(),
+    OperatorPacket {
       operator,
+      sub_packets,
       ..
     } => {
-      let sub_vals = sub_packets.iter().map(eval);
-      let sub_vals_vec: Vec<u64> = sub_vals.clone().collect();
-      match operator {
-        Sum => sub_vals.sum::<u64>(),
-        Product => sub_vals.product::<u64>(),
-        Min => sub_vals.min().unwrap(),
-        Max => sub_vals.max().unwrap(),
-        GreaterThan => {
-          if sub_vals_vec[0] > sub_vals_vec[1] {
-            1
-          } else {
-            0
-          }
-        }
-        LessThan => {
-          if sub_vals_vec[0] < sub_vals_vec[1] {
-            1
-          } else {
-            0
-          }
-        }
-        EqualTo => {
-          if sub_vals_vec[0] == sub_vals_vec[1] {
-            1
-          } else {
-            0
-          }
-        }
+      // Sums and products are associative, so only operands that bind looser need parentheses
+      let operand_precedence = match operator {
+        Min | Max => 0,
+        _ if sub_packets.len() == 1 => min_precedence,
+        _ => precedence(packet).max(2),
+      };
+      let operands: Vec<String> = sub_packets
+        .iter()
+        .map(|p| infix_with(p, operand_precedence))
+        .collect();
+      match (operator, &operands[..]) {
+        (Min | Max, _) => format!("{}({})", operator_name(*operator), operands.join(", ")),
+        (Sum, []) => String::from("0"),
+        (Product, []) => String::from("1"),
+        (_, [single]) => return single.clone(),
+        (Sum, _) => operands.join(" + "),
+        (Product, _) => operands.join(" * "),
+        (GreaterThan, _) => operands.join(" > "),
+        (LessThan, _) => operands.join(" < "),
+        (EqualTo, _) => operands.join(" == "),
       }
     }
+  };
+  if precedence(packet) < min_precedence {
+    format!("({})", res)
+  } else {
+    res
   }
 }
 
-// Apply a parser to the first X bits of the input,
-fn parse_with_length<'a, T, F>(
-  count: usize,
-  mut f: F,
-) -> impl FnMut((&'a [u8], usize)) -> IResult<(&'a [u8], usize), T>
-where
-  F: FnMut((&'a [u8], usize)) -> IResult<(&'a [u8], usize), T>,
-{
-  move |(input, offset)| {
-    // Calculate how many bytes the left and right side of the split will contain
-    let total_size_left = offset + count;
-    let left_segments = (total_size_left - 1) / 8 + 1;
-
-    let input_segments = input.len();
-    let total_size_right = input_segments * 8 - total_size_left;
-    let right_segments = (total_size_right - 1) / 8 + 1;
-    let right_offset = total_size_left % 8;
-
-    // Split the input into two sides
-    let left: &[u8] = &input[0..left_segments];
-    let right: &[u8] = &input[input_segments - right_segments..input_segments];
-
-    // Apply the parser to the left side
-    let (cont_l, inner) = f((left, offset))?;
-    // Ensure the complete left side has been consumed by the supplied parser
-    let (cont_l, _): (_, u8) = if right_offset > 0 {
-      bits::take(8 - right_offset)(cont_l)
-    } else {
-      Ok((cont_l, 0))
-    }?;
-    let (_, _) = eof(cont_l)?;
-
-    // Return the result found in the left side, and continue with the right side
-    Ok(((right, right_offset), inner))
-  }
-}
-
-fn hex_to_bytes(input: &str) -> Result<Vec<u8>, std::num::ParseIntError> {
-  (0..input.len())
-    .step_by(2)
-    .map(|i| u8::from_str_radix(&input[i..=i + 1], 16))
-    .collect()
+// The index of the first hexadecimal digit with a 1 after the bit at `pos`
+fn first_non_padding(hex: &str, pos: usize) -> Option<usize> {
+  hex.char_indices().skip(pos / 4).find_map(|(i, c)| {
+    // Only the bits after `pos` count in the digit that holds it
+    let mask = if i == pos / 4 { (1 << (4 - pos % 4)) - 1 } else { 0xF };
+    (c.to_digit(16)? & mask != 0).then_some(i)
+  })
 }
 
-pub struct Day16;
+// The packet as an expression in infix notation, like "min(3, 7) + (5 > 2) * 9"
+pub fn infix(packet: &Packet) -> String {
+  infix_with(packet, 0)
+}
 
 impl Day for Day16 {
   type Input = Packet;
 
   fn parse(input: &str) -> IResult<&str, Self::Input> {
-    let input_vec = hex_to_bytes(input).unwrap();
-    let (_, package) = parse_transmission((&input_vec, 0)).unwrap();
-    Ok(("", package))
+    let (cont, hex) = hex_digit1(input)?;
+    // Errors are reported at the hexadecimal digit that holds the bit they happened at
+    let mut reader = BitReader::new(hex.as_bytes());
+    let packet = decode_packet(&mut reader).map_err(|e| {
+      let kind = match e {
+        DecodeErr::End { .. } => ErrorKind::Eof,
+        _ => ErrorKind::Verify,
+      };
+      nom::Err::Error(Error::new(&input[e.pos() / 4..], kind))
+    })?;
+    // Only zeroes may follow the packet
+    if let Some(i) = first_non_padding(hex, reader.pos()) {
+      return Err(nom::Err::Error(Error::new(&input[i..], ErrorKind::Verify)));
+    }
+    Ok((cont, packet))
   }
 
   type Output1 = usize;
 
-  fn part_1(input: &Self::Input) -> Self::Output1 {
-    sum_versions(input)
+  fn part_1(input: &Self::Input) -> Result<Self::Output1, NoSolution> {
+    Ok(sum_versions(input))
   }
 
-  type Output2 = u64;
+  type Output2 = BigUint;
 
-  fn part_2(input: &Self::Input) -> Self::Output2 {
+  fn part_2(input: &Self::Input) -> Result<Self::Output2, NoSolution> {
     eval(input)
   }
 }
+
+impl Generate for Day16 {
+  fn generate<R: Rng>(rng: &mut R, scale: usize) -> String {
+    // A sum packet around random packet trees, with no more sub-packets than length type 1 can
+    // count, so that it always fits one of the length types
+    let packet = OperatorPacket {
+      version: rng.gen_range(0..8),
+      operator: Sum,
+      sub_packets: (0..(4 * scale).min(2047)).map(|_| generate_packet(rng, 6)).collect(),
+    };
+    // Half of the operator packets inside get length type 1, the others length type 0 if their
+    // sub-packets fit in it. The outer packet is left to `encode_with`.
+    let mut bits = vec![];
+    let encoded = encode_with(&packet, &mut bits, &mut |p| {
+      if std::ptr::eq(p, &packet) || rng.gen_bool(0.5) {
+        None
+      } else {
+        Some(Count)
+      }
+    });
+    encoded.expect("every packet has a version below 8 and sub-packets length type 1 can count");
+    bits_to_hex(bits)
+  }
+}
+
+impl Print for Day16 {
+  // Operator packets get length type 1 whenever it can count their sub-packets, which has a shorter
+  // header than length type 0, and literals are written without leading zero groups. So no packet
+  // gets longer than it was in the transmission it was parsed from, and packets with too many
+  // sub-packets for length type 1 still fit the 15 bits of length type 0 they came with.
+  fn print(input: &Self::Input) -> String {
+    let mut bits = vec![];
+    encode_with(input, &mut bits, &mut |p| match p {
+      OperatorPacket { sub_packets, .. } if sub_packets.len() < 1 << 11 => Some(Count),
+      _ => Some(Bits),
+    })
+    .expect("a parsed packet gets no longer when it is encoded again");
+    bits_to_hex(bits)
+  }
+}
+
+impl Explore for Day16 {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn literal(version: u8, value: u64) -> Packet {
+    Literal {
+      version,
+      value: BigUint::from(value),
+    }
+  }
+
+  #[test]
+  fn encodes_the_examples() {
+    assert_eq!(to_hex(&literal(6, 2021), None), Ok(String::from("D2FE28")));
+    let packet = OperatorPacket {
+      version: 1,
+      operator: LessThan,
+      sub_packets: vec![literal(6, 10), literal(2, 20)],
+    };
+    assert_eq!(to_hex(&packet, Some(Bits)), Ok(String::from("38006F45291200")));
+    let packet = OperatorPacket {
+      version: 7,
+      operator: Max,
+      sub_packets: vec![literal(2, 1), literal(4, 2), literal(1, 3)],
+    };
+    assert_eq!(to_hex(&packet, Some(Count)), Ok(String::from("EE00D40C823060")));
+  }
+
+  #[test]
+  fn both_length_types_decode_to_the_same_packet() {
+    let packet = OperatorPacket {
+      version: 3,
+      operator: Sum,
+      sub_packets: vec![
+        literal(0, 0),
+        OperatorPacket {
+          version: 5,
+          operator: Product,
+          sub_packets: vec![literal(1, 0xFFFF), literal(2, 7)],
+        },
+      ],
+    };
+    for length_type in [Bits, Count] {
+      let hex = to_hex(&packet, Some(length_type)).unwrap();
+      assert_eq!(decode(hex.as_bytes()).unwrap(), packet);
+    }
+  }
+
+  #[test]
+  fn refuses_digits_after_the_packet() {
+    assert!(Day16::parse("D2FE28").is_ok());
+    assert!(Day16::parse("D2FE2800").is_ok());
+    let input = "D2FE28ABCDEF";
+    assert_eq!(
+      Day16::parse(input),
+      Err(nom::Err::Error(Error::new(&input[6..], ErrorKind::Verify)))
+    );
+    // The literal ends 3 bits into the last digit, so its final bit must be 0 too
+    assert!(Day16::parse("D2FE29").is_err());
+  }
+
+  #[test]
+  fn prints_packets_that_grow_with_length_type_0() {
+    // The length type 1 packets inside get 4 bits longer with length type 0, which makes the
+    // sub-packets of the outer packet too long for it, while it has too many for length type 1
+    let sum = |sub_packets| OperatorPacket {
+      version: 0,
+      operator: Sum,
+      sub_packets,
+    };
+    let mut sub_packets: Vec<Packet> = (0..2047).map(|_| literal(0, 0)).collect();
+    sub_packets.push(sum((0..340).map(|_| sum(vec![literal(0, 0)])).collect()));
+    let packet = sum(sub_packets);
+    let mut bits = vec![];
+    encode_with(&packet, &mut bits, &mut |p| {
+      Some(if std::ptr::eq(p, &packet) { Bits } else { Count })
+    })
+    .unwrap();
+    let (_, parsed) = Day16::parse(&bits_to_hex(bits)).unwrap();
+    assert!(to_hex(&parsed, None).is_err());
+    assert_eq!(Day16::parse(&Day16::print(&parsed)).unwrap().1, parsed);
+  }
+
+  #[test]
+  fn refuses_packets_that_do_not_fit() {
+    assert_eq!(to_hex(&literal(8, 1), None), Err(EncodeErr::Version(8)));
+    let packet = OperatorPacket {
+      version: 0,
+      operator: Sum,
+      sub_packets: (0..2048).map(|_| literal(0, 1 << 60)).collect(),
+    };
+    assert_eq!(to_hex(&packet, Some(Count)), Err(EncodeErr::TooMany(2048)));
+    assert_eq!(to_hex(&packet, Some(Bits)), Err(EncodeErr::TooLong(2048 * 86)));
+    // Without a length type, the error of the fallback to length type 1 is given
+    assert_eq!(to_hex(&packet, None), Err(EncodeErr::TooMany(2048)));
+  }
+}
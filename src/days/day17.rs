@@ -1,42 +1,36 @@
-use nom::bytes::complete::tag;
-use nom::character::complete::i32;
-use nom::sequence::tuple;
+use crate::geometry::{Box2, Vec2};
+use crate::parser::{labelled, range_inclusive};
+use nom::sequence::pair;
 use nom::IResult;
-use std::cmp::Ordering::*;
+use rand::Rng;
+use std::fmt::{self, Display, Formatter};
 
-use crate::days::Day;
+use crate::days::{Day, Explore, Generate, NoSolution, Print};
 
 pub struct Day17;
 
-fn parse_range(input: &str) -> IResult<&str, (i32, i32)> {
-  let (cont, (min, _, max)) = tuple((i32, tag(".."), i32))(input)?;
-
-  Ok((cont, (min, max)))
-}
-
 #[derive(Debug)]
 pub struct State {
-  pos: (i32, i32),
-  velocity: (i32, i32),
+  pos: Vec2<i32>,
+  velocity: Vec2<i32>,
 }
 
+#[derive(Debug, PartialEq)]
 pub struct Trench {
-  min_x: i32,
-  max_x: i32,
-  min_y: i32,
-  max_y: i32,
+  area: Box2<i32>,
 }
 
+impl Display for Trench {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    let Box2 { min, max } = self.area;
+    write!(f, "target area: x={}..{}, y={}..{}", min.x, max.x, min.y, max.y)
+  }
+}
+
+// Drag slows the probe down horizontally, gravity pulls it down
 fn step(s: &mut State) {
-  let (x, y) = s.pos;
-  let (dx, dy) = s.velocity;
-  let new_dx = match dx.cmp(&0) {
-    Less => dx + 1,
-    Greater => dx - 1,
-    Equal => dx,
-  };
-  s.pos = (x + dx, y + dy);
-  s.velocity = (new_dx, dy - 1);
+  s.pos += s.velocity;
+  s.velocity -= Vec2::new(s.velocity.x.signum(), 1);
 }
 
 #[derive(Debug)]
@@ -46,20 +40,20 @@ pub enum Missed {
   Through,
 }
 
-fn check_velocity(t: &Trench, velocity: (i32, i32)) -> Result<i32, Missed> {
-  let pos: (i32, i32) = (0, 0);
-  let mut s = State { pos, velocity };
+fn check_velocity(t: &Trench, velocity: Vec2<i32>) -> Result<i32, Missed> {
+  let mut s = State { pos: Vec2::zero(), velocity };
+  let (min, max) = (t.area.min, t.area.max);
   let mut highest_y = 0;
   loop {
-    if s.pos.1 > highest_y {
-      highest_y = s.pos.1;
+    if s.pos.y > highest_y {
+      highest_y = s.pos.y;
     }
-    if s.pos.0 >= t.min_x && s.pos.0 <= t.max_x && s.pos.1 >= t.min_y && s.pos.1 <= t.max_y {
+    if t.area.contains(s.pos) {
       return Ok(highest_y);
-    } else if s.pos.1 < t.min_y && s.velocity.1 <= 0 {
-      if s.pos.0 < t.min_x {
+    } else if s.pos.y < min.y && s.velocity.y <= 0 {
+      if s.pos.x < min.x {
         return Err(Missed::Left);
-      } else if s.pos.0 > t.max_x {
+      } else if s.pos.x > max.x {
         return Err(Missed::Right);
       } else {
         return Err(Missed::Through);
@@ -71,13 +65,12 @@ fn check_velocity(t: &Trench, velocity: (i32, i32)) -> Result<i32, Missed> {
 }
 
 fn solve(t: &Trench) -> (i32, i32) {
-  let x_start = 1;
-  let x_end = t.max_x;
+  let (min, max) = (t.area.min, t.area.max);
   let mut highest_y = 0;
   let mut count = 0;
-  for x in x_start..=x_end {
-    for y in t.min_y..i32::abs(t.min_y) {
-      match check_velocity(t, (x, y)) {
+  for x in 1..=max.x {
+    for y in min.y..i32::abs(min.y) {
+      match check_velocity(t, Vec2::new(x, y)) {
         Ok(high_y) => {
           if high_y > highest_y {
             highest_y = high_y;
@@ -96,30 +89,42 @@ impl Day for Day17 {
   type Input = Trench;
 
   fn parse(input: &str) -> IResult<&str, Self::Input> {
-    let (cont, _) = tag("target area: x=")(input)?;
-    let (cont, (min_x, max_x)) = parse_range(cont)?;
-    let (cont, _) = tag(", y=")(cont)?;
-    let (cont, (min_y, max_y)) = parse_range(cont)?;
-    Ok((
-      cont,
-      Trench {
-        min_x,
-        max_x,
-        min_y,
-        max_y,
-      },
-    ))
+    let (cont, (xs, ys)) = pair(
+      labelled("target area: x=", range_inclusive),
+      labelled(", y=", range_inclusive),
+    )(input)?;
+    let area = Box2::new(Vec2::new(*xs.start(), *ys.start()), Vec2::new(*xs.end(), *ys.end()));
+    Ok((cont, Trench { area }))
   }
 
   type Output1 = i32;
 
-  fn part_1(input: &Self::Input) -> Self::Output1 {
-    solve(input).0
+  fn part_1(input: &Self::Input) -> Result<Self::Output1, NoSolution> {
+    Ok(solve(input).0)
   }
 
   type Output2 = i32;
 
-  fn part_2(input: &Self::Input) -> Self::Output2 {
-    solve(input).1
+  fn part_2(input: &Self::Input) -> Result<Self::Output2, NoSolution> {
+    Ok(solve(input).1)
   }
 }
+
+impl Generate for Day17 {
+  fn generate<R: Rng>(rng: &mut R, scale: usize) -> String {
+    let scale = scale as i32;
+    let min_x = rng.gen_range(20..250) * scale;
+    let max_x = min_x + rng.gen_range(10..60) * scale;
+    let min_y = rng.gen_range(-150..-50) * scale;
+    let max_y = i32::min(-5, min_y + rng.gen_range(10..40) * scale);
+    format!("target area: x={}..{}, y={}..{}", min_x, max_x, min_y, max_y)
+  }
+}
+
+impl Print for Day17 {
+  fn print(input: &Self::Input) -> String {
+    input.to_string()
+  }
+}
+
+impl Explore for Day17 {}
@@ -1,22 +1,38 @@
+use crate::parser::{lines, print_list, MyErr};
 use itertools::Itertools;
 use nom::branch::alt;
 use nom::bytes::complete::tag;
 use nom::character::complete::i32;
-use nom::character::complete::newline;
+use nom::combinator::all_consuming;
 use nom::combinator::map as pmap;
-use nom::multi::separated_list0;
+use nom::combinator::verify;
 use nom::sequence::delimited;
 use nom::sequence::separated_pair;
 use nom::IResult;
+use rand::Rng;
+use std::fmt::{self, Display, Formatter};
+use std::iter::Sum;
+use std::ops::Add;
+use std::str::FromStr;
 
-use crate::days::Day;
+use crate::days::{Day, Explore, Generate, NoSolution, Print};
 
-#[derive(Debug, Clone)]
+// Snailfish numbers as a tree of pairs
+#[derive(Debug, Clone, PartialEq)]
 pub enum SnailfishNumber {
   Num(i32),
   Pair(Box<Self>, Box<Self>),
 }
 
+impl Display for SnailfishNumber {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match self {
+      SnailfishNumber::Num(x) => write!(f, "{}", x),
+      SnailfishNumber::Pair(l, r) => write!(f, "[{},{}]", l, r),
+    }
+  }
+}
+
 fn parse_num(input: &str) -> IResult<&str, SnailfishNumber> {
   pmap(i32, SnailfishNumber::Num)(input)
 }
@@ -34,6 +50,20 @@ fn parse_snailfish_number(input: &str) -> IResult<&str, SnailfishNumber> {
   alt((parse_num, parse_pair))(input)
 }
 
+// The number of pairs the deepest regular number is nested in
+fn depth(input: &SnailfishNumber) -> usize {
+  match input {
+    SnailfishNumber::Num(_) => 0,
+    SnailfishNumber::Pair(l, r) => 1 + depth(l).max(depth(r)),
+  }
+}
+
+// Snailfish numbers in the homework are reduced, so none of their pairs are nested inside four
+// pairs. Adding two of them then never nests a pair deeper than one explosion can undo.
+fn parse_reduced(input: &str) -> IResult<&str, SnailfishNumber> {
+  verify(parse_snailfish_number, |n| depth(n) <= 4)(input)
+}
+
 fn add_first_left(input: &mut SnailfishNumber, n: i32) {
   use SnailfishNumber::*;
   match input {
@@ -104,79 +134,395 @@ fn split(input: &mut SnailfishNumber) -> bool {
   }
 }
 
-fn step(input: &mut SnailfishNumber) -> bool {
-  explode_1(input, 0).is_some() || split(input)
+// What a step of reducing did
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReduceStep {
+  Explode,
+  Split,
 }
 
-fn reduce(input: &mut SnailfishNumber) {
-  let after_step = step(input);
-  if after_step {
-    reduce(input);
+impl Display for ReduceStep {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match self {
+      ReduceStep::Explode => write!(f, "after explode"),
+      ReduceStep::Split => write!(f, "after split"),
+    }
   }
 }
 
-fn add(x1: &SnailfishNumber, x2: &SnailfishNumber) -> SnailfishNumber {
-  use SnailfishNumber::*;
-  let mut new_pair = Pair(Box::new(x1.clone()), Box::new(x2.clone()));
-  reduce(&mut new_pair);
-  new_pair
+impl SnailfishNumber {
+  pub fn pair(left: SnailfishNumber, right: SnailfishNumber) -> SnailfishNumber {
+    SnailfishNumber::Pair(Box::new(left), Box::new(right))
+  }
+
+  pub fn magnitude(&self) -> i32 {
+    match self {
+      SnailfishNumber::Num(x) => *x,
+      SnailfishNumber::Pair(l, r) => 3 * l.magnitude() + 2 * r.magnitude(),
+    }
+  }
+
+  // Does the first step of reducing the number: exploding the leftmost pair that can explode, or
+  // else splitting the leftmost regular number that can split. None if the number is reduced.
+  pub fn step(&mut self) -> Option<ReduceStep> {
+    if explode_1(self, 0).is_some() {
+      Some(ReduceStep::Explode)
+    } else if split(self) {
+      Some(ReduceStep::Split)
+    } else {
+      None
+    }
+  }
+
+  pub fn reduce(&mut self) {
+    while self.step().is_some() {}
+  }
+
+  // Reduces the number, and returns every step with the number after it, like the examples of
+  // the puzzle
+  pub fn reduce_traced(&mut self) -> Vec<(ReduceStep, SnailfishNumber)> {
+    let mut res = vec![];
+    while let Some(step) = self.step() {
+      res.push((step, self.clone()));
+    }
+    res
+  }
 }
 
-fn calculate_magnitude(input: &SnailfishNumber) -> i32 {
-  use SnailfishNumber::*;
-  match input {
-    Num(x) => *x,
-    Pair(l, r) => 3 * calculate_magnitude(&*l.clone()) + 2 * calculate_magnitude(&*r.clone()),
+// The reduced sum
+impl Add for SnailfishNumber {
+  type Output = SnailfishNumber;
+
+  fn add(self, other: SnailfishNumber) -> SnailfishNumber {
+    let mut res = SnailfishNumber::pair(self, other);
+    res.reduce();
+    res
   }
 }
 
-fn sum_slice(input: &[SnailfishNumber]) -> SnailfishNumber {
-  use SnailfishNumber::*;
-  input
-    .iter()
-    .fold(None, |res, x| match res {
-      None => Some(x.clone()),
-      Some(res) => Some(add(&res, x)),
-    })
-    .unwrap_or(Num(0))
+impl Add for &SnailfishNumber {
+  type Output = SnailfishNumber;
+
+  fn add(self, other: &SnailfishNumber) -> SnailfishNumber {
+    self.clone() + other.clone()
+  }
+}
+
+// Adds the numbers from first to last. There is no snailfish number that leaves others the same
+// when added, so the sum of none is the regular number 0.
+impl Sum for SnailfishNumber {
+  fn sum<I: Iterator<Item = SnailfishNumber>>(mut iter: I) -> SnailfishNumber {
+    match iter.next() {
+      Some(first) => iter.fold(first, |acc, x| acc + x),
+      None => SnailfishNumber::Num(0),
+    }
+  }
+}
+
+impl<'a> Sum<&'a SnailfishNumber> for SnailfishNumber {
+  fn sum<I: Iterator<Item = &'a SnailfishNumber>>(iter: I) -> SnailfishNumber {
+    iter.cloned().sum()
+  }
+}
+
+// Any snailfish number in its canonical form, also one that is not reduced
+impl FromStr for SnailfishNumber {
+  type Err = MyErr;
+
+  fn from_str(s: &str) -> Result<SnailfishNumber, MyErr> {
+    let (_, res) = all_consuming(parse_snailfish_number)(s)?;
+    Ok(res)
+  }
+}
+
+// A regular number and the number of pairs it is nested in
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Regular {
+  value: i32,
+  depth: u8,
+}
+
+// Snailfish numbers as their regular numbers from left to right, which is all that reducing
+// needs. Exploding and splitting change the vector in place instead of rebuilding boxes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlatNumber(Vec<Regular>);
+
+impl From<&SnailfishNumber> for FlatNumber {
+  fn from(number: &SnailfishNumber) -> FlatNumber {
+    fn flatten(number: &SnailfishNumber, depth: u8, res: &mut Vec<Regular>) {
+      match number {
+        SnailfishNumber::Num(value) => res.push(Regular {
+          value: *value,
+          depth,
+        }),
+        SnailfishNumber::Pair(l, r) => {
+          flatten(l, depth + 1, res);
+          flatten(r, depth + 1, res);
+        }
+      }
+    }
+    let mut res = vec![];
+    flatten(number, 0, &mut res);
+    FlatNumber(res)
+  }
+}
+
+impl FlatNumber {
+  fn explode(&mut self) -> bool {
+    // Sums of reduced numbers nest pairs in at most four pairs, so the first regular number nested
+    // in five is the left of a pair of two regular numbers
+    let Some(i) = self.0.iter().position(|r| r.depth > 4) else {
+      return false;
+    };
+    let (left, right) = (self.0[i].value, self.0[i + 1].value);
+    if i > 0 {
+      self.0[i - 1].value += left;
+    }
+    if let Some(next) = self.0.get_mut(i + 2) {
+      next.value += right;
+    }
+    self.0[i] = Regular {
+      value: 0,
+      depth: self.0[i].depth - 1,
+    };
+    self.0.remove(i + 1);
+    true
+  }
+
+  fn split(&mut self) -> bool {
+    let Some(i) = self.0.iter().position(|r| r.value >= 10) else {
+      return false;
+    };
+    let Regular { value, depth } = self.0[i];
+    self.0[i] = Regular {
+      value: value / 2,
+      depth: depth + 1,
+    };
+    let right = Regular {
+      value: (value + 1) / 2,
+      depth: depth + 1,
+    };
+    self.0.insert(i + 1, right);
+    true
+  }
+
+  fn reduce(&mut self) {
+    while self.explode() || self.split() {}
+  }
+
+  // The magnitude of the element starting at regular number `*i`, which is nested in `depth`
+  // pairs, and moves `*i` past it
+  fn magnitude_from(&self, i: &mut usize, depth: u8) -> i32 {
+    if self.0[*i].depth == depth {
+      *i += 1;
+      return self.0[*i - 1].value;
+    }
+    let left = self.magnitude_from(i, depth + 1);
+    let right = self.magnitude_from(i, depth + 1);
+    3 * left + 2 * right
+  }
+
+  fn write_from(&self, f: &mut Formatter, i: &mut usize, depth: u8) -> fmt::Result {
+    if self.0[*i].depth == depth {
+      *i += 1;
+      return write!(f, "{}", self.0[*i - 1].value);
+    }
+    write!(f, "[")?;
+    self.write_from(f, i, depth + 1)?;
+    write!(f, ",")?;
+    self.write_from(f, i, depth + 1)?;
+    write!(f, "]")
+  }
+}
+
+impl Display for FlatNumber {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    self.write_from(f, &mut 0, 0)
+  }
+}
+
+// A representation of snailfish numbers that can solve both parts, so that representations can be
+// compared
+pub trait Snailfish: Clone + Display {
+  // Parses a reduced snailfish number
+  fn parse(input: &str) -> IResult<&str, Self>;
+  // The reduced sum
+  fn add(&self, other: &Self) -> Self;
+  fn magnitude(&self) -> i32;
+}
+
+impl Snailfish for SnailfishNumber {
+  fn parse(input: &str) -> IResult<&str, Self> {
+    parse_reduced(input)
+  }
+
+  fn add(&self, other: &Self) -> Self {
+    self + other
+  }
+
+  fn magnitude(&self) -> i32 {
+    SnailfishNumber::magnitude(self)
+  }
+}
+
+impl Snailfish for FlatNumber {
+  fn parse(input: &str) -> IResult<&str, Self> {
+    pmap(parse_reduced, |n| FlatNumber::from(&n))(input)
+  }
+
+  fn add(&self, other: &Self) -> Self {
+    let mut res = FlatNumber(Vec::with_capacity(self.0.len() + other.0.len()));
+    for r in self.0.iter().chain(&other.0) {
+      res.0.push(Regular {
+        value: r.value,
+        depth: r.depth + 1,
+      });
+    }
+    res.reduce();
+    res
+  }
+
+  fn magnitude(&self) -> i32 {
+    self.magnitude_from(&mut 0, 0)
+  }
+}
+
+// The magnitude of the sum of all numbers, or 0 if there are none
+pub fn sum_magnitude<S: Snailfish>(numbers: &[S]) -> i32 {
+  let mut iter = numbers.iter();
+  let Some(first) = iter.next() else {
+    return 0;
+  };
+  iter.fold(first.clone(), |acc, x| acc.add(x)).magnitude()
+}
+
+// The largest magnitude of the sum of two different numbers, in either order
+pub fn largest_magnitude<S: Snailfish>(numbers: &[S]) -> i32 {
+  let mut max = 0;
+  for pair in numbers.iter().combinations(2) {
+    let (x1, x2) = (pair[0], pair[1]);
+    max = max.max(x1.add(x2).magnitude()).max(x2.add(x1).magnitude());
+  }
+  max
 }
 
 pub struct Day18;
 
+// Pairs nested inside four pairs would explode, so those are never generated
+fn generate_number<R: Rng>(rng: &mut R, depth: usize) -> String {
+  if depth >= 4 || (depth > 0 && rng.gen_bool(0.3)) {
+    rng.gen_range(0..10).to_string()
+  } else {
+    format!(
+      "[{},{}]",
+      generate_number(rng, depth + 1),
+      generate_number(rng, depth + 1)
+    )
+  }
+}
+
 impl Day for Day18 {
-  type Input = Vec<SnailfishNumber>;
+  type Input = Vec<FlatNumber>;
 
   fn parse(input: &str) -> IResult<&str, Self::Input> {
-    separated_list0(newline, parse_snailfish_number)(input)
+    lines(FlatNumber::parse)(input)
   }
 
   type Output1 = i32;
 
-  fn part_1(input: &Self::Input) -> Self::Output1 {
-    let res = sum_slice(input);
-    calculate_magnitude(&res)
+  fn part_1(input: &Self::Input) -> Result<Self::Output1, NoSolution> {
+    Ok(sum_magnitude(input))
   }
 
   type Output2 = i32;
 
-  fn part_2(input: &Self::Input) -> Self::Output2 {
-    let pairs = input.iter().combinations(2);
-    let mut max = 0;
-    for p in pairs {
-      if let [x1, x2] = p[0..=1] {
-        let mag1 = calculate_magnitude(&add(x1, x2));
-        let mag2 = calculate_magnitude(&add(x2, x1));
-        if mag1 > max {
-          max = mag1;
-        }
-        if mag2 > max {
-          max = mag2;
-        }
-      } else {
-        panic!("Pairs element was not actually a pair")
-      }
-    }
+  fn part_2(input: &Self::Input) -> Result<Self::Output2, NoSolution> {
+    Ok(largest_magnitude(input))
+  }
+}
+
+impl Generate for Day18 {
+  fn generate<R: Rng>(rng: &mut R, scale: usize) -> String {
+    (0..100 * scale)
+      .map(|_| generate_number(rng, 0))
+      .collect::<Vec<String>>()
+      .join("\n")
+  }
+}
+
+impl Print for Day18 {
+  fn print(input: &Self::Input) -> String {
+    print_list(input, "\n")
+  }
+}
+
+impl Explore for Day18 {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn number(s: &str) -> SnailfishNumber {
+    s.parse().unwrap()
+  }
+
+  #[test]
+  fn reduces_like_the_example() {
+    let mut sum = SnailfishNumber::pair(
+      number("[[[[4,3],4],4],[7,[[8,4],9]]]"),
+      number("[1,1]"),
+    );
+    let steps: Vec<String> = sum
+      .reduce_traced()
+      .iter()
+      .map(|(step, n)| format!("{}: {}", step, n))
+      .collect();
+    assert_eq!(
+      steps,
+      [
+        "after explode: [[[[0,7],4],[7,[[8,4],9]]],[1,1]]",
+        "after explode: [[[[0,7],4],[15,[0,13]]],[1,1]]",
+        "after split: [[[[0,7],4],[[7,8],[0,13]]],[1,1]]",
+        "after split: [[[[0,7],4],[[7,8],[0,[6,7]]]],[1,1]]",
+        "after explode: [[[[0,7],4],[[7,8],[6,0]]],[8,1]]",
+      ]
+    );
+  }
+
+  #[test]
+  fn sums_and_magnitudes() {
+    let total: SnailfishNumber = ["[1,1]", "[2,2]", "[3,3]", "[4,4]", "[5,5]", "[6,6]"]
+      .iter()
+      .map(|s| number(s))
+      .sum();
+    assert_eq!(total.to_string(), "[[[[5,0],[7,4]],[5,5]],[6,6]]");
+    assert_eq!(total.magnitude(), 1137);
+    assert_eq!(
+      &number("[[[[4,3],4],4],[7,[[8,4],9]]]") + &number("[1,1]"),
+      number("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]")
+    );
+    assert_eq!(Vec::<SnailfishNumber>::new().into_iter().sum::<SnailfishNumber>(), number("0"));
+  }
+
+  #[test]
+  fn parses_only_whole_numbers() {
+    assert!("[1,2]x".parse::<SnailfishNumber>().is_err());
+    assert!("[1,2".parse::<SnailfishNumber>().is_err());
+  }
 
-    max
+  #[test]
+  fn representations_agree() {
+    let lines = [
+      "[[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]",
+      "[[[5,[2,8]],4],[5,[[9,9],0]]]",
+      "[6,[[[6,2],[5,6]],[[7,6],[4,7]]]]",
+      "[[[6,[0,7]],[0,9]],[4,[9,[9,0]]]]",
+    ];
+    let tree: Vec<SnailfishNumber> = lines.iter().map(|s| number(s)).collect();
+    let flat: Vec<FlatNumber> = tree.iter().map(FlatNumber::from).collect();
+    assert_eq!(sum_magnitude(&tree), sum_magnitude(&flat));
+    assert_eq!(largest_magnitude(&tree), largest_magnitude(&flat));
+    let tree_sum = &tree[0] + &tree[1];
+    assert_eq!(Snailfish::add(&flat[0], &flat[1]).to_string(), tree_sum.to_string());
   }
 }
@@ -1,21 +1,23 @@
+use crate::geometry::{Box3, Rotation, Vec3};
+use crate::parser::{blocks, lines};
 use itertools::Itertools;
+use log::debug;
 use nom::bytes::complete::tag;
 use nom::character::complete::i32;
 use nom::character::complete::newline;
-use nom::multi::separated_list0;
-use nom::sequence::pair;
 use nom::sequence::tuple;
 use nom::IResult;
-use std::collections::HashMap;
+use rand::seq::SliceRandom;
+use rand::Rng;
 use std::collections::HashSet;
 
-use crate::days::Day;
+use crate::days::{Day, Explore, Generate, NoSolution, Print};
 
 pub struct Day19;
 
-type Pos = (i32, i32, i32);
+type Pos = Vec3<i32>;
 
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Scanner {
   points: HashSet<Pos>,
   distances: HashSet<i32>,
@@ -23,12 +25,12 @@ pub struct Scanner {
 
 fn parse_vec3(input: &str) -> IResult<&str, Pos> {
   let (cont, (x, _, y, _, z)) = tuple((i32, tag(","), i32, tag(","), i32))(input)?;
-  Ok((cont, (x, y, z)))
+  Ok((cont, Vec3::new(x, y, z)))
 }
 
 fn parse_scanner(input: &str) -> IResult<&str, Scanner> {
   let (cont, _) = tuple((tag("--- scanner "), i32, tag(" ---"), newline))(input)?;
-  let (cont, poss) = separated_list0(newline, parse_vec3)(cont)?;
+  let (cont, poss) = lines(parse_vec3)(cont)?;
   let points = HashSet::from_iter(poss);
   let distances = calculate_distances(&points);
   Ok((cont, Scanner { points, distances }))
@@ -38,95 +40,18 @@ fn calculate_distances(points: &HashSet<Pos>) -> HashSet<i32> {
   points
     .iter()
     .combinations(2)
-    .map(|ps| {
-      let dx = ps[0].0 - ps[1].0;
-      let dy = ps[0].1 - ps[1].1;
-      let dz = ps[0].2 - ps[1].2;
-      let dist = (((dx * dx + dy * dy + dz * dz) as f32).sqrt() * 100000.0).round() as i32;
-      dist
-    })
+    .map(|ps| (ps[0].euclidean_distance(*ps[1]) * 100000.0).round() as i32)
     .collect()
 }
 
-fn min3((x1, y1, z1): &Pos, (x2, y2, z2): &Pos) -> Pos {
-  (x1 - x2, y1 - y2, z1 - z2)
-}
-
-enum Axis {
-  X,
-  Y,
-  Z,
-}
-
-enum SignedAxis {
-  Pos(Axis),
-  Neg(Axis),
-}
-
-fn get_unsigned_value(a: &Axis, pos: &Pos) -> i32 {
-  match a {
-    Axis::X => pos.0,
-    Axis::Y => pos.1,
-    Axis::Z => pos.2,
-  }
-}
-
-fn get_value(sa: &SignedAxis, pos: &Pos) -> i32 {
-  match sa {
-    SignedAxis::Pos(a) => get_unsigned_value(a, pos),
-    SignedAxis::Neg(a) => -get_unsigned_value(a, pos),
-  }
-}
-
-type Rotation = (SignedAxis, SignedAxis, SignedAxis);
-
-fn get_all_rotations() -> Vec<Rotation> {
-  use Axis::*;
-  use SignedAxis::*;
-  vec![
-    (Pos(X), Pos(Y), Pos(Z)),
-    (Pos(X), Neg(Y), Neg(Z)),
-    (Neg(X), Pos(Y), Neg(Z)),
-    (Neg(X), Neg(Y), Pos(Z)),
-    (Pos(X), Pos(Z), Neg(Y)),
-    (Pos(X), Neg(Z), Pos(Y)),
-    (Neg(X), Pos(Z), Pos(Y)),
-    (Neg(X), Neg(Z), Neg(Y)),
-    (Pos(Y), Pos(Z), Pos(X)),
-    (Pos(Y), Neg(Z), Neg(X)),
-    (Neg(Y), Pos(Z), Neg(X)),
-    (Neg(Y), Neg(Z), Pos(X)),
-    (Pos(Y), Pos(X), Neg(Z)),
-    (Pos(Y), Neg(X), Pos(Z)),
-    (Neg(Y), Pos(X), Pos(Z)),
-    (Neg(Y), Neg(X), Neg(Z)),
-    (Pos(Z), Pos(X), Pos(Y)),
-    (Pos(Z), Neg(X), Neg(Y)),
-    (Neg(Z), Pos(X), Neg(Y)),
-    (Neg(Z), Neg(X), Pos(Y)),
-    (Pos(Z), Pos(Y), Neg(X)),
-    (Pos(Z), Neg(Y), Pos(X)),
-    (Neg(Z), Pos(Y), Pos(X)),
-    (Neg(Z), Neg(Y), Neg(X)),
-  ]
-}
-
-fn rotate((rx, ry, rz): &Rotation, pos: &Pos) -> Pos {
-  (get_value(rx, pos), get_value(ry, pos), get_value(rz, pos))
-}
-
 fn find_overlap(scanner0: &Scanner, scanner1: &Scanner) -> Option<(Pos, Scanner)> {
-  let all_rotations = get_all_rotations();
+  let all_rotations = Rotation::all();
   if scanner0.distances.intersection(&scanner1.distances).count() >= 56 {
     for p0 in scanner0.points.iter().skip(11) {
       for p1 in scanner1.points.iter().skip(11) {
         for rotation in &all_rotations {
-          let p_base = rotate(rotation, &p1);
-          let p_diff = min3(&p_base, &p0);
-          let transformed_slice = scanner1.points.iter().map(|p| {
-            let p_rotated = rotate(rotation, p);
-            min3(&p_rotated, &p_diff)
-          });
+          let p_diff = rotation.apply(*p1) - *p0;
+          let transformed_slice = scanner1.points.iter().map(|p| rotation.apply(*p) - p_diff);
           if transformed_slice
             .clone()
             .filter(|p| scanner0.points.contains(p))
@@ -135,7 +60,7 @@ fn find_overlap(scanner0: &Scanner, scanner1: &Scanner) -> Option<(Pos, Scanner)
           {
             let points = transformed_slice.collect();
             let distances = scanner1.distances.clone();
-            return Some((min3(&(0, 0, 0), &p_diff), Scanner { points, distances }));
+            return Some((-p_diff, Scanner { points, distances }));
           }
         }
       }
@@ -144,10 +69,6 @@ fn find_overlap(scanner0: &Scanner, scanner1: &Scanner) -> Option<(Pos, Scanner)
   None
 }
 
-fn manhattan_distance(p1: &Pos, p2: &Pos) -> i32 {
-  i32::abs(p2.0 - p1.0) + i32::abs(p2.1 - p1.1) + i32::abs(p2.2 - p1.2)
-}
-
 fn helper(
   total_scanner: &mut Scanner,
   scanner_positions: &mut Vec<Pos>,
@@ -160,6 +81,11 @@ fn helper(
       Some((scanner_pos, s1)) => {
         scanner_positions.push(scanner_pos);
         total_scanner.points.extend(&s1.points);
+        debug!(
+          "Merged scanner at {}, {} beacons known so far",
+          scanner_pos,
+          total_scanner.points.len()
+        );
         total_scanner.distances = calculate_distances(&total_scanner.points);
       }
     }
@@ -167,40 +93,145 @@ fn helper(
   res
 }
 
-fn combine_all(input: &[Scanner]) -> (usize, Vec<Pos>) {
-  let mut start_scanner = input[0].clone();
-  let mut list: Vec<Scanner> = input[1..].to_vec();
-  let mut positions: Vec<Pos> = vec![(0, 0, 0)];
+fn combine_all(input: &[Scanner]) -> Result<(usize, Vec<Pos>), NoSolution> {
+  let (first, rest) = input
+    .split_first()
+    .ok_or_else(|| NoSolution(String::from("there are no scanners")))?;
+  let mut start_scanner = first.clone();
+  let mut list: Vec<Scanner> = rest.to_vec();
+  let mut positions: Vec<Pos> = vec![Vec3::zero()];
   while !list.is_empty() {
     let new_list = helper(&mut start_scanner, &mut positions, &list);
+    if new_list.len() == list.len() {
+      return Err(NoSolution(format!(
+        "{} scanners do not overlap with the others",
+        list.len()
+      )));
+    }
     list = new_list;
   }
-  (start_scanner.points.len(), positions)
+  Ok((start_scanner.points.len(), positions))
+}
+
+const RANGE: i32 = 1000;
+
+fn is_visible(scanner: &Pos, beacon: &Pos) -> bool {
+  Box3::around(*scanner, RANGE).contains(*beacon)
+}
+
+// Adds random beacons inside the area until `required` of them are visible from all scanners
+fn add_beacons<R: Rng>(
+  rng: &mut R,
+  beacons: &mut HashSet<Pos>,
+  scanners: &[Pos],
+  Box3 { min, max }: Box3<i32>,
+  required: usize,
+) {
+  let visible = |beacons: &HashSet<Pos>| {
+    beacons
+      .iter()
+      .filter(|b| scanners.iter().all(|s| is_visible(s, b)))
+      .count()
+  };
+  while visible(beacons) < required {
+    beacons.insert(Vec3::new(
+      rng.gen_range(min.x..=max.x),
+      rng.gen_range(min.y..=max.y),
+      rng.gen_range(min.z..=max.z),
+    ));
+  }
 }
 
 impl Day for Day19 {
   type Input = Vec<Scanner>;
 
   fn parse(input: &str) -> IResult<&str, Self::Input> {
-    separated_list0(pair(newline, newline), parse_scanner)(input)
+    blocks(parse_scanner)(input)
   }
 
   type Output1 = usize;
 
-  fn part_1(input: &Self::Input) -> Self::Output1 {
-    let (res, _) = combine_all(input);
-    res
+  fn part_1(input: &Self::Input) -> Result<Self::Output1, NoSolution> {
+    let (res, _) = combine_all(input)?;
+    Ok(res)
   }
 
   type Output2 = i32;
 
-  fn part_2(input: &Self::Input) -> Self::Output2 {
-    let (_, positions) = combine_all(input);
+  fn part_2(input: &Self::Input) -> Result<Self::Output2, NoSolution> {
+    let (_, positions) = combine_all(input)?;
     positions
       .iter()
       .combinations(2)
-      .map(|xs| manhattan_distance(xs[0], xs[1]))
+      .map(|xs| xs[0].manhattan_distance(*xs[1]))
       .max()
-      .unwrap()
+      .ok_or_else(|| NoSolution(String::from("there is only one scanner")))
+  }
+}
+
+impl Generate for Day19 {
+  fn generate<R: Rng>(rng: &mut R, scale: usize) -> String {
+    let all_rotations = Rotation::all();
+    let mut beacons = HashSet::new();
+    let mut scanners: Vec<Pos> = vec![Vec3::zero()];
+    add_beacons(rng, &mut beacons, &scanners, Box3::around(Vec3::zero(), RANGE), 26);
+
+    // Every scanner shares at least 12 beacons with a scanner placed before it
+    for _ in 1..30 * scale {
+      let parent = scanners[rng.gen_range(0..scanners.len())];
+      let mut offset = [0; 3].map(|_| rng.gen_range(-150..=150));
+      let axis = rng.gen_range(0..3);
+      offset[axis] = rng.gen_range(900..=1100) * if rng.gen_bool(0.5) { 1 } else { -1 };
+      let pos = parent + Vec3::new(offset[0], offset[1], offset[2]);
+      let cube = Box3::around(pos, RANGE);
+      let shared = Box3::around(parent, RANGE)
+        .intersection(&cube)
+        .expect("Scanners are placed close enough to see the same area");
+      add_beacons(rng, &mut beacons, &[parent, pos], shared, 12);
+      add_beacons(rng, &mut beacons, &[pos], cube, 25);
+      scanners.push(pos);
+    }
+
+    let mut beacons: Vec<Pos> = beacons.into_iter().collect();
+    beacons.sort_unstable();
+    scanners
+      .iter()
+      .enumerate()
+      .map(|(i, scanner)| {
+        // Scanner 0 defines the orientation, the others are rotated randomly
+        let rotation = &all_rotations[if i == 0 { 0 } else { rng.gen_range(0..24) }];
+        let mut visible: Vec<Pos> = beacons
+          .iter()
+          .filter(|b| is_visible(scanner, b))
+          .map(|b| rotation.apply(*b - *scanner))
+          .collect();
+        visible.shuffle(rng);
+        let lines: Vec<String> = visible.iter().map(|b| b.to_string()).collect();
+        format!("--- scanner {} ---\n{}", i, lines.join("\n"))
+      })
+      .collect::<Vec<String>>()
+      .join("\n\n")
   }
 }
+
+impl Print for Day19 {
+  fn print(input: &Self::Input) -> String {
+    input
+      .iter()
+      .enumerate()
+      .map(|(i, scanner)| {
+        let mut points: Vec<&Pos> = scanner.points.iter().collect();
+        points.sort_unstable();
+        let points = points
+          .iter()
+          .map(|p| p.to_string())
+          .collect::<Vec<String>>()
+          .join("\n");
+        format!("--- scanner {} ---\n{}", i, points)
+      })
+      .collect::<Vec<String>>()
+      .join("\n\n")
+  }
+}
+
+impl Explore for Day19 {}
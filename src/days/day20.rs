@@ -1,11 +1,13 @@
-use crate::days::Day;
-use crate::parser::grid;
-use nom::branch::alt;
-use nom::bytes::complete::tag;
-use nom::character::complete::newline;
-use nom::combinator::map as pmap;
+use crate::days::{Day, Explore, Frame, Generate, NoSolution, Param, Print, Visualize};
+use crate::parser::{blank_line, char_map, grid, Grid};
+use log::trace;
 use nom::multi::many1;
+use nom::sequence::separated_pair;
 use nom::IResult;
+use rand::Rng;
+use std::borrow::Cow;
+use std::fmt::{self, Display, Formatter};
+use std::iter::{from_fn, once};
 
 pub struct Day20;
 
@@ -17,20 +19,28 @@ pub enum Pixel {
 
 use Pixel::*;
 
+impl Display for Pixel {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match self {
+      Light => write!(f, "#"),
+      Dark => write!(f, "."),
+    }
+  }
+}
+
+const PIXELS: [(char, Pixel); 2] = [('#', Light), ('.', Dark)];
+
 fn parse_pixel(input: &str) -> IResult<&str, Pixel> {
-  alt((pmap(tag("#"), |_| Light), pmap(tag("."), |_| Dark)))(input)
+  char_map(&PIXELS)(input)
 }
 
-fn get_index(input: &[Vec<Pixel>], px: isize, py: isize, default: Pixel) -> usize {
+fn get_index(input: &Grid<Pixel>, px: isize, py: isize, default: Pixel) -> usize {
   let mut res = 0;
-  let input_width = input[0].len() as isize;
-  let input_height = input.len() as isize;
   for y in py - 1..=py + 1 {
     for x in px - 1..=px + 1 {
-      let pixel = if y >= 0 && y < input_height && x >= 0 && x < input_width {
-        input[y as usize][x as usize]
-      } else {
-        default
+      let pixel = match (usize::try_from(x), usize::try_from(y)) {
+        (Ok(x), Ok(y)) => input.get((x, y)).copied().unwrap_or(default),
+        _ => default,
       };
       let digit = match pixel {
         Light => 1,
@@ -43,87 +53,148 @@ fn get_index(input: &[Vec<Pixel>], px: isize, py: isize, default: Pixel) -> usiz
   res
 }
 
-fn step(input: &[Vec<Pixel>], algorithm: &[Pixel], default: Pixel) -> Vec<Vec<Pixel>> {
-  let input_width = input[0].len() as isize;
-  let input_height = input.len() as isize;
-  let mut new_image = vec![];
+// The image grows by one pixel on every side
+fn step(input: &Grid<Pixel>, algorithm: &[Pixel], default: Pixel) -> Grid<Pixel> {
+  Grid::from_fn(input.width() + 2, input.height() + 2, |(x, y)| {
+    algorithm[get_index(input, x as isize - 1, y as isize - 1, default)]
+  })
+}
 
-  for y in -1..input_height + 1 {
-    let mut row = vec![];
-    for x in -1..input_width + 1 {
-      let pixel = &algorithm[get_index(input, x, y, default)];
-      row.push(*pixel);
-    }
-    new_image.push(row);
+// The value of all pixels outside of the image after applying the algorithm
+fn next_default(algorithm: &[Pixel], default: Pixel) -> Pixel {
+  match default {
+    Dark => algorithm[0],
+    Light => algorithm[511],
   }
-
-  new_image
 }
 
 fn repeat_steps(
-  input: Vec<Vec<Pixel>>,
+  input: Grid<Pixel>,
   algorithm: &[Pixel],
   default: Pixel,
   n: usize,
   i: usize,
-) -> Vec<Vec<Pixel>> {
+) -> Grid<Pixel> {
   if i >= n {
     input
   } else {
-    let new_default = match default {
-      Dark => algorithm[0],
-      Light => algorithm[511],
-    };
-    repeat_steps(
-      step(&input, algorithm, default),
-      algorithm,
-      new_default,
-      n,
-      i + 1,
-    )
+    let new_default = next_default(algorithm, default);
+    let new_image = step(&input, algorithm, default);
+    trace!("Image after step {}:\n{}", i + 1, image_to_string(&new_image));
+    repeat_steps(new_image, algorithm, new_default, n, i + 1)
   }
 }
 
-fn count_lights(input: &[Vec<Pixel>]) -> usize {
+fn count_lights(input: &Grid<Pixel>) -> usize {
+  input.iter().filter(|x| **x == Light).count()
+}
+
+fn image_to_string(input: &Grid<Pixel>) -> String {
   input
-    .iter()
-    .map(|r| r.iter().filter(|x| **x == Light).count())
-    .sum::<usize>()
+    .map(|p| match p {
+      Light => '█',
+      Dark => '░',
+    })
+    .to_string()
 }
 
-fn _print_image(input: &[Vec<Pixel>]) {
-  for row in input {
-    for p in row {
-      match p {
-        Light => print!("█"),
-        Dark => print!("░"),
-      }
-    }
-    println!();
-  }
+fn to_frame(input: &Grid<Pixel>) -> Frame {
+  input
+    .rows()
+    .map(|row| {
+      row
+        .iter()
+        .map(|p| match p {
+          Light => ('█', None),
+          Dark => ('░', None),
+        })
+        .collect()
+    })
+    .collect()
 }
 
 impl Day for Day20 {
-  type Input = (Vec<Pixel>, Vec<Vec<Pixel>>);
+  type Input = (Vec<Pixel>, Grid<Pixel>);
 
   fn parse(input: &str) -> IResult<&str, Self::Input> {
-    let (cont, algorithm) = many1(parse_pixel)(input)?;
-    let (cont, _) = many1(newline)(cont)?;
-    let (cont, image) = grid(parse_pixel)(cont)?;
-    Ok((cont, (algorithm, image)))
+    separated_pair(many1(parse_pixel), blank_line, grid(parse_pixel))(input)
   }
 
   type Output1 = usize;
 
-  fn part_1((algorithm, image): &Self::Input) -> Self::Output1 {
+  fn part_1((algorithm, image): &Self::Input) -> Result<Self::Output1, NoSolution> {
     let result = repeat_steps(image.clone(), algorithm, Dark, 2, 0);
-    count_lights(&result)
+    Ok(count_lights(&result))
   }
 
   type Output2 = usize;
 
-  fn part_2((algorithm, image): &Self::Input) -> Self::Output2 {
+  fn part_2((algorithm, image): &Self::Input) -> Result<Self::Output2, NoSolution> {
     let result = repeat_steps(image.clone(), algorithm, Dark, 50, 0);
-    count_lights(&result)
+    Ok(count_lights(&result))
+  }
+}
+
+impl Visualize for Day20 {
+  // The image before enhancing, and after each of the 50 enhancements of part 2
+  fn frames((algorithm, image): &Self::Input) -> Box<dyn Iterator<Item = Frame> + '_> {
+    let mut image = image.clone();
+    let mut default = Dark;
+    let mut i = 0;
+    Box::new(once(to_frame(&image)).chain(from_fn(move || {
+      if i >= 50 {
+        return None;
+      }
+      image = step(&image, algorithm, default);
+      default = next_default(algorithm, default);
+      i += 1;
+      Some(to_frame(&image))
+    })))
+  }
+}
+
+impl Generate for Day20 {
+  fn generate<R: Rng>(rng: &mut R, scale: usize) -> String {
+    let mut algorithm: Vec<char> = (0..512)
+      .map(|_| if rng.gen_bool(0.5) { '#' } else { '.' })
+      .collect();
+    // If the infinite dark area lights up, it has to go dark again in the next step
+    if algorithm[0] == '#' {
+      algorithm[511] = '.';
+    }
+    let size = 100 * scale;
+    let image: Vec<String> = (0..size)
+      .map(|_| {
+        (0..size)
+          .map(|_| if rng.gen_bool(0.5) { '#' } else { '.' })
+          .collect()
+      })
+      .collect();
+    format!(
+      "{}\n\n{}",
+      algorithm.into_iter().collect::<String>(),
+      image.join("\n")
+    )
+  }
+}
+
+impl Print for Day20 {
+  fn print(input: &Self::Input) -> String {
+    let (algorithm, image) = input;
+    let algorithm = algorithm.iter().map(|p| p.to_string()).collect::<String>();
+    format!("{}\n\n{}", algorithm, image)
+  }
+}
+
+impl Explore for Day20 {
+  const PARAMS: &'static [Param] = &[Param {
+    name: Cow::Borrowed("iterations"),
+    part_1: 2,
+    part_2: 50,
+  }];
+
+  fn run_with((algorithm, image): &Self::Input, values: &[usize]) -> Result<String, NoSolution> {
+    let result = repeat_steps(image.clone(), algorithm, Dark, values[0], 0);
+    Ok(count_lights(&result).to_string())
   }
 }
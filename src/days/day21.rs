@@ -1,11 +1,11 @@
-use nom::bytes::complete::tag;
+use crate::parser::{int, labelled};
 use nom::character::complete::newline;
-use nom::character::complete::u32;
-use nom::sequence::tuple;
+use nom::sequence::separated_pair;
 use nom::IResult;
+use rand::Rng;
 use std::collections::HashMap;
 
-use crate::days::Day;
+use crate::days::{Day, Explore, Generate, NoSolution, Print};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Player {
@@ -54,7 +54,6 @@ fn new_state(state: &State, roll: u32) -> State {
       P1 => state.p2,
       P2 => run_roll(&state.p2, roll),
     },
-    ..*state
   }
 }
 
@@ -100,8 +99,11 @@ impl Day for Day21 {
   type Input = State;
 
   fn parse(input: &str) -> IResult<&str, Self::Input> {
-    let (cont, (_, p1_pos, _)) = tuple((tag("Player 1 starting position: "), u32, newline))(input)?;
-    let (cont, (_, p2_pos)) = tuple((tag("Player 2 starting position: "), u32))(cont)?;
+    let (cont, (p1_pos, p2_pos)) = separated_pair(
+      labelled("Player 1 starting position: ", int),
+      newline,
+      labelled("Player 2 starting position: ", int),
+    )(input)?;
 
     let p1 = Player {
       position: p1_pos,
@@ -123,8 +125,8 @@ impl Day for Day21 {
 
   type Output1 = u32;
 
-  fn part_1(input: &Self::Input) -> Self::Output1 {
-    let mut state = input.clone();
+  fn part_1(input: &Self::Input) -> Result<Self::Output1, NoSolution> {
+    let mut state = *input;
 
     let mut die_index = 0;
     let mut die_rolls = 0;
@@ -136,7 +138,7 @@ impl Day for Day21 {
       state.p1.position = (state.p1.position + die_roll - 1) % 10 + 1;
       state.p1.score += state.p1.position;
       if state.p1.score >= 1000 {
-        return die_rolls * state.p2.score;
+        return Ok(die_rolls * state.p2.score);
       }
 
       die_rolls += 3;
@@ -145,16 +147,38 @@ impl Day for Day21 {
       state.p2.position = (state.p2.position + die_roll - 1) % 10 + 1;
       state.p2.score += state.p2.position;
       if state.p2.score >= 1000 {
-        return state.p1.score * die_rolls;
+        return Ok(state.p1.score * die_rolls);
       }
     }
   }
 
   type Output2 = u64;
 
-  fn part_2(input: &Self::Input) -> Self::Output2 {
+  fn part_2(input: &Self::Input) -> Result<Self::Output2, NoSolution> {
     let mut memo = HashMap::new();
     let (p1, p2) = quantum_dice(*input, &mut memo);
-    u64::max(p1, p2)
+    Ok(u64::max(p1, p2))
   }
 }
+
+impl Generate for Day21 {
+  // The game has a fixed size, so the scale is ignored
+  fn generate<R: Rng>(rng: &mut R, _scale: usize) -> String {
+    format!(
+      "Player 1 starting position: {}\nPlayer 2 starting position: {}",
+      rng.gen_range(1..=10),
+      rng.gen_range(1..=10)
+    )
+  }
+}
+
+impl Print for Day21 {
+  fn print(input: &Self::Input) -> String {
+    format!(
+      "Player 1 starting position: {}\nPlayer 2 starting position: {}",
+      input.p1.position, input.p2.position
+    )
+  }
+}
+
+impl Explore for Day21 {}
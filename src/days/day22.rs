@@ -1,15 +1,15 @@
+use crate::geometry::{Box3, Vec3};
+use crate::parser::{labelled, lines, print_list, range_inclusive};
 use nom::branch::alt;
 use nom::bytes::complete::tag;
-use nom::character::complete::i64;
-use nom::character::complete::newline;
 use nom::combinator::map as pmap;
-use nom::multi::separated_list0;
 use nom::sequence::tuple;
 use nom::IResult;
-use std::cmp::{max, min};
+use rand::Rng;
 use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
 
-use crate::days::Day;
+use crate::days::{Day, Explore, Generate, NoSolution, Print};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum OnOff {
@@ -17,149 +17,154 @@ pub enum OnOff {
   Off,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Instruction {
   action: OnOff,
-  min_x: i64,
-  max_x: i64,
-  min_y: i64,
-  max_y: i64,
-  min_z: i64,
-  max_z: i64,
+  cuboid: Box3<i64>,
 }
 
 use OnOff::*;
 
-fn parse_line(input: &str) -> IResult<&str, Instruction> {
-  let (cont, action) = alt((pmap(tag("on"), |_| On), pmap(tag("off"), |_| Off)))(input)?;
-  let (cont, (_, min_x, _, max_x)) = tuple((tag(" x="), i64, tag(".."), i64))(cont)?;
-  let (cont, (_, min_y, _, max_y)) = tuple((tag(",y="), i64, tag(".."), i64))(cont)?;
-  let (cont, (_, min_z, _, max_z)) = tuple((tag(",z="), i64, tag(".."), i64))(cont)?;
-  Ok((
-    cont,
-    Instruction {
-      action,
-      min_x,
-      max_x,
-      min_y,
-      max_y,
-      min_z,
-      max_z,
-    },
-  ))
-}
-
-fn has_overlap(on: &Instruction, off: &Instruction) -> bool {
-  off.max_x >= on.min_x
-    && off.min_x <= on.max_x
-    && off.max_y >= on.min_y
-    && off.min_y <= on.max_y
-    && off.max_z >= on.min_z
-    && off.min_z <= on.max_z
+impl Display for Instruction {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    let Box3 { min, max } = self.cuboid;
+    write!(
+      f,
+      "{} x={}..{},y={}..{},z={}..{}",
+      match self.action {
+        On => "on",
+        Off => "off",
+      },
+      min.x,
+      max.x,
+      min.y,
+      max.y,
+      min.z,
+      max.z
+    )
+  }
 }
 
-fn push_valid(res: &mut Vec<Instruction>, i: Instruction) {
-  if i.max_x >= i.min_x
-    && i.max_y >= i.min_y
-    && i.max_z >= i.min_z
-  {
-    res.push(i);
-  }
+fn parse_line(input: &str) -> IResult<&str, Instruction> {
+  let (cont, action) = alt((pmap(tag("on"), |_| On), pmap(tag("off"), |_| Off)))(input)?;
+  let (cont, (xs, ys, zs)) = tuple((
+    labelled(" x=", range_inclusive),
+    labelled(",y=", range_inclusive),
+    labelled(",z=", range_inclusive),
+  ))(cont)?;
+  let cuboid = Box3::new(
+    Vec3::new(*xs.start(), *ys.start(), *zs.start()),
+    Vec3::new(*xs.end(), *ys.end(), *zs.end()),
+  );
+  Ok((cont, Instruction { action, cuboid }))
 }
 
-fn split_cuboid(a: &Instruction, b: &Instruction) -> Vec<Instruction> {
-  if has_overlap(a, b) {
-    let mut res = vec![];
-    let xs = [
-      (a.min_x, b.min_x - 1),
-      (max(a.min_x, b.min_x), min(a.max_x, b.max_x)),
-      (b.max_x + 1, a.max_x),
-    ];
-    let ys = [
-      (a.min_y, b.min_y - 1),
-      (max(a.min_y, b.min_y), min(a.max_y, b.max_y)),
-      (b.max_y + 1, a.max_y),
-    ];
-    let zs = [
-      (a.min_z, b.min_z - 1),
-      (max(a.min_z, b.min_z), min(a.max_z, b.max_z)),
-      (b.max_z + 1, a.max_z),
-    ];
-    for x in xs {
-      for y in ys {
-        for z in zs {
-          if !(x == xs[1] && y == ys[1] && z == zs[1]) {
-            push_valid(
-              &mut res,
-              Instruction {
-                action: On,
-                min_x: x.0,
-                max_x: x.1,
-                min_y: y.0,
-                max_y: y.1,
-                min_z: z.0,
-                max_z: z.1,
-              },
-            );
-          }
+// The parts of `a` outside of `b`, as up to 26 cuboids around their intersection
+fn split_cuboid(a: &Box3<i64>, b: &Box3<i64>) -> Vec<Box3<i64>> {
+  let overlap = match a.intersection(b) {
+    Some(overlap) => overlap,
+    None => return vec![*a],
+  };
+  let (min, max) = (a.min.to_array(), a.max.to_array());
+  let (o_min, o_max) = (overlap.min.to_array(), overlap.max.to_array());
+  let [xs, ys, zs] =
+    [0, 1, 2].map(|i| [(min[i], o_min[i] - 1), (o_min[i], o_max[i]), (o_max[i] + 1, max[i])]);
+  let mut res = vec![];
+  for x in xs {
+    for y in ys {
+      for z in zs {
+        let part = Box3::new(Vec3::new(x.0, y.0, z.0), Vec3::new(x.1, y.1, z.1));
+        if part != overlap && !part.is_empty() {
+          res.push(part);
         }
       }
     }
-    res
-  } else {
-    vec![*a]
   }
+  res
 }
 
-fn run_instructions(insts: &[Instruction]) -> Vec<Instruction> {
-  let mut total_instructions = vec![];
+// The disjoint cuboids that are on after all instructions
+fn run_instructions(insts: &[Instruction]) -> Vec<Box3<i64>> {
+  let mut cuboids = vec![];
   for i in insts {
-    total_instructions = total_instructions
+    cuboids = cuboids
       .iter()
-      .map(|i2| split_cuboid(i2, i))
-      .flatten()
+      .flat_map(|c| split_cuboid(c, &i.cuboid))
       .collect();
     if let On = i.action {
-      total_instructions.push(*i);
+      cuboids.push(i.cuboid);
     }
   }
-  total_instructions
+  cuboids
 }
 
 pub struct Day22;
 
+fn generate_range<R: Rng>(rng: &mut R, min: i64, max: i64, size: i64) -> (i64, i64) {
+  let from = rng.gen_range(min..=max - size);
+  (from, rng.gen_range(from..=from + size))
+}
+
 impl Day for Day22 {
   type Input = Vec<Instruction>;
 
   fn parse(input: &str) -> IResult<&str, Self::Input> {
-    separated_list0(newline, parse_line)(input)
+    lines(parse_line)(input)
   }
 
   type Output1 = usize;
 
-  fn part_1(input: &Self::Input) -> Self::Output1 {
-    let mut hm: HashMap<(i64, i64, i64), OnOff> = HashMap::new();
+  fn part_1(input: &Self::Input) -> Result<Self::Output1, NoSolution> {
+    let init_area = Box3::around(Vec3::zero(), 50);
+    let mut hm: HashMap<Vec3<i64>, OnOff> = HashMap::new();
     for i in input {
-      for x in max(i.min_x, -50)..=min(i.max_x, 50) {
-        for y in max(i.min_y, -50)..=min(i.max_y, 50) {
-          for z in max(i.min_z, -50)..=min(i.max_z, 50) {
-            hm.insert((x, y, z), i.action);
+      if let Some(Box3 { min, max }) = i.cuboid.intersection(&init_area) {
+        for x in min.x..=max.x {
+          for y in min.y..=max.y {
+            for z in min.z..=max.z {
+              hm.insert(Vec3::new(x, y, z), i.action);
+            }
           }
         }
       }
     }
-    hm.iter().filter(|(_, s)| **s == On).count()
+    Ok(hm.iter().filter(|(_, s)| **s == On).count())
   }
 
   type Output2 = i64;
 
-  fn part_2(input: &Self::Input) -> Self::Output2 {
-    let split_insts = run_instructions(input);
-    split_insts
-      .iter()
+  fn part_2(input: &Self::Input) -> Result<Self::Output2, NoSolution> {
+    Ok(run_instructions(input).iter().map(Box3::volume).sum())
+  }
+}
+
+impl Generate for Day22 {
+  fn generate<R: Rng>(rng: &mut R, scale: usize) -> String {
+    // The first instructions lie within the initialisation area of part 1
+    let instructions: Vec<Instruction> = (0..20 + 400 * scale)
       .map(|i| {
-        ((i.max_x - i.min_x + 1) * (i.max_y - i.min_y + 1) * (i.max_z - i.min_z + 1)) as i64
+        let (min, max, size) = if i < 20 {
+          (-50, 50, 50)
+        } else {
+          (-100000, 100000, 40000)
+        };
+        let (x1, x2) = generate_range(rng, min, max, size);
+        let (y1, y2) = generate_range(rng, min, max, size);
+        let (z1, z2) = generate_range(rng, min, max, size);
+        Instruction {
+          action: if rng.gen_bool(0.6) { On } else { Off },
+          cuboid: Box3::new(Vec3::new(x1, y1, z1), Vec3::new(x2, y2, z2)),
+        }
       })
-      .sum::<i64>()
+      .collect();
+    print_list(&instructions, "\n")
   }
 }
+
+impl Print for Day22 {
+  fn print(input: &Self::Input) -> String {
+    print_list(input, "\n")
+  }
+}
+
+impl Explore for Day22 {}
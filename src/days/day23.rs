@@ -1,11 +1,11 @@
-use crate::days::Day;
-use crate::parser::grid;
-use nom::branch::alt;
-use nom::bytes::complete::tag;
-use nom::combinator::map as pmap;
-use nom::multi::many0;
+use crate::days::{Day, Explore, Generate, NoSolution, Print};
+use crate::parser::{char_map, print_grid};
+use log::{debug, trace};
+use nom::character::complete::newline;
+use nom::multi::{many0, many1, separated_list1};
 use nom::IResult;
 use pathfinding::directed::dijkstra::dijkstra;
+use std::fmt::{self, Display, Formatter};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Amphipod {
@@ -16,6 +16,8 @@ pub enum Amphipod {
 }
 
 use Amphipod::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Tile {
@@ -32,16 +34,33 @@ pub struct State<const DIM: usize> {
 
 use Tile::*;
 
+impl Display for Tile {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    let c = match self {
+      Pod(A) => 'A',
+      Pod(B) => 'B',
+      Pod(C) => 'C',
+      Pod(D) => 'D',
+      Open => '.',
+      // Parsing does not distinguish the spaces around the burrow from walls
+      Wall => '#',
+    };
+    write!(f, "{}", c)
+  }
+}
+
+const TILES: [(char, Tile); 7] = [
+  ('A', Pod(A)),
+  ('B', Pod(B)),
+  ('C', Pod(C)),
+  ('D', Pod(D)),
+  ('#', Wall),
+  (' ', Wall),
+  ('.', Open),
+];
+
 fn parse_tile(input: &str) -> IResult<&str, Tile> {
-  alt((
-    pmap(tag("A"), |_| Pod(A)),
-    pmap(tag("B"), |_| Pod(B)),
-    pmap(tag("C"), |_| Pod(C)),
-    pmap(tag("D"), |_| Pod(D)),
-    pmap(tag("#"), |_| Wall),
-    pmap(tag(" "), |_| Wall),
-    pmap(tag("."), |_| Open),
-  ))(input)
+  char_map(&TILES)(input)
 }
 
 fn init_state<const DIM: usize>(input: &[Vec<Tile>]) -> State<DIM> {
@@ -77,6 +96,7 @@ const COSTS: [usize; 4] = [1, 10, 100, 1000];
 const VALID_XS: [usize; 7] = [1, 2, 4, 6, 8, 10, 11];
 
 fn get_successors2<const DIM: usize>(state: &State<DIM>) -> Vec<(State<DIM>, usize)> {
+  trace!("Expanding {:?}", state);
   let mut states = vec![];
   for (i, room) in state.rooms.iter().enumerate() {
     for j in 0..DIM {
@@ -112,13 +132,7 @@ fn get_successors2<const DIM: usize>(state: &State<DIM>) -> Vec<(State<DIM>, usi
             }
 
             if reachable {
-              let steps = j
-                + 1
-                + if x > x_start {
-                  x - x_start
-                } else {
-                  x_start - x
-                };
+              let steps = j + 1 + x.abs_diff(x_start);
               let cost = steps * COSTS[p as usize];
               let mut new_state = state.clone();
               new_state.hallway[x] = Some(p);
@@ -172,13 +186,7 @@ fn get_successors2<const DIM: usize>(state: &State<DIM>) -> Vec<(State<DIM>, usi
       }
 
       if reachable {
-        let steps = y_goal
-          + 1
-          + if x_goal > x_start {
-            x_goal - x_start
-          } else {
-            x_start - x_goal
-          };
+        let steps = y_goal + 1 + x_goal.abs_diff(x_start);
         let cost = steps * COSTS[p as usize];
         let mut new_state = state.clone();
         new_state.rooms[p as usize][y_goal] = Some(p);
@@ -198,40 +206,120 @@ fn success2<const DIM: usize>(state: &State<DIM>) -> bool {
     && state.rooms[3].iter().all(|mp| mp == &Some(D))
 }
 
+// Returns the lowest cost to organise the amphipods, or None if they cannot be organised
+fn solve<const DIM: usize>(input: &[Vec<Tile>]) -> Option<usize> {
+  let (path, cost) = dijkstra(&init_state::<DIM>(input), get_successors2::<DIM>, success2)?;
+  debug!("Found a solution of {} moves", path.len() - 1);
+  Some(cost)
+}
+
+// Inserts the two folded rows of part 2
+fn unfold(input: &[Vec<Tile>]) -> Vec<Vec<Tile>> {
+  let mut input = input.to_vec();
+  let (_, row_1) = many0(parse_tile)("  #D#C#B#A#").unwrap();
+  let (_, row_2) = many0(parse_tile)("  #D#B#A#C#").unwrap();
+
+  input.insert(3, row_1);
+  input.insert(4, row_2);
+  input
+}
+
 pub struct Day23;
 
 impl Day for Day23 {
   type Input = Vec<Vec<Tile>>;
 
   fn parse(input: &str) -> IResult<&str, Self::Input> {
-    // Ok(("", String::from(input)))
-    grid(parse_tile)(input)
+    // The rows below the hallway are shorter, so this is no `Grid`
+    separated_list1(newline, many1(parse_tile))(input)
   }
 
   type Output1 = usize;
 
-  fn part_1(input: &Self::Input) -> Self::Output1 {
-    dijkstra(&init_state(input), |s| get_successors2::<2>(s), success2)
-      .unwrap()
-      .1
+  fn part_1(input: &Self::Input) -> Result<Self::Output1, NoSolution> {
+    solve::<2>(input)
+      .ok_or_else(|| NoSolution(String::from("the amphipods can not be organised")))
   }
 
   type Output2 = usize;
 
-  fn part_2(input: &Self::Input) -> Self::Output2 {
-    let mut input = input.clone();
-    let (_, row_1) = many0(parse_tile)("  #D#C#B#A#").unwrap();
-    let (_, row_2) = many0(parse_tile)("  #D#B#A#C#").unwrap();
+  fn part_2(input: &Self::Input) -> Result<Self::Output2, NoSolution> {
+    solve::<4>(&unfold(input))
+      .ok_or_else(|| NoSolution(String::from("the amphipods can not be organised")))
+  }
+}
 
-    input.insert(3, row_1);
-    input.insert(4, row_2);
+impl Generate for Day23 {
+  // The burrow has a fixed size, so the scale is ignored
+  fn generate<R: Rng>(rng: &mut R, _scale: usize) -> String {
+    // Not every arrangement can be organised once the rows of part 2 are inserted, so keep
+    // shuffling until one can
+    loop {
+      let mut pods = ['A', 'A', 'B', 'B', 'C', 'C', 'D', 'D'];
+      pods.shuffle(rng);
+      let res = format!(
+        "#############\n#...........#\n###{}#{}#{}#{}###\n  #{}#{}#{}#{}#\n  #########",
+        pods[0], pods[1], pods[2], pods[3], pods[4], pods[5], pods[6], pods[7]
+      );
+      let (_, input) = Self::parse(&res).unwrap();
+      if solve::<2>(&input).is_some() && solve::<4>(&unfold(&input)).is_some() {
+        return res;
+      }
+    }
+  }
+}
+
+impl Print for Day23 {
+  fn print(input: &Self::Input) -> String {
+    print_grid(input)
+  }
+}
+
+impl Explore for Day23 {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const EXAMPLE: &str = "#############
+#...........#
+###B#C#B#D###
+  #A#D#C#A#
+  #########";
+
+  #[test]
+  fn unfolds_the_rows_of_part_2() {
+    let (_, input) = Day23::parse(EXAMPLE).unwrap();
+    let unfolded = "#############
+#...........#
+###B#C#B#D###
+  #D#C#B#A#
+  #D#B#A#C#
+  #A#D#C#A#
+  #########";
+    let (_, unfolded) = Day23::parse(unfolded).unwrap();
+    assert_eq!(print_grid(&unfold(&input)), print_grid(&unfolded));
+  }
+
+  #[test]
+  fn solves_the_example() {
+    let (_, input) = Day23::parse(EXAMPLE).unwrap();
+    assert_eq!(Day23::part_1(&input), Ok(12521));
+    assert_eq!(Day23::part_2(&input), Ok(44169));
+  }
 
-    dijkstra(
-      &init_state::<4>(&input),
-      |s| get_successors2::<4>(s),
-      success2,
+  #[test]
+  fn reports_burrows_that_can_not_be_organised() {
+    // Part 1 can organise this burrow, but not once the rows of part 2 are inserted
+    let (_, input) = Day23::parse(
+      "#############
+#...........#
+###A#A#D#B###
+  #C#C#D#B#
+  #########",
     )
-    .unwrap()
-    .1
+    .unwrap();
+    assert!(Day23::part_1(&input).is_ok());
+    assert!(Day23::part_2(&input).is_err());
   }
 }
@@ -1,16 +1,16 @@
-use core::ops::Range;
+use crate::parser::{char_map, int, lines, print_list};
+use log::trace;
 use nom::branch::alt;
 use nom::bytes::complete::tag;
-use nom::character::complete::i32;
-use nom::character::complete::newline;
 use nom::character::complete::space1;
 use nom::combinator::map as pmap;
-use nom::multi::separated_list0;
 use nom::sequence::tuple;
 use nom::IResult;
+use rand::Rng;
 use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display, Formatter};
 
-use crate::days::Day;
+use crate::days::{Day, Explore, Generate, NoSolution, Print};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Var {
@@ -20,13 +20,13 @@ pub enum Var {
   Z,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Val {
   Var(Var),
   Int(i32),
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Stmt {
   Inp(Var),
   Add(Var, Val),
@@ -36,18 +36,49 @@ pub enum Stmt {
   Eql(Var, Val),
 }
 
+impl Display for Var {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    let c = match self {
+      Var::W => 'w',
+      Var::X => 'x',
+      Var::Y => 'y',
+      Var::Z => 'z',
+    };
+    write!(f, "{}", c)
+  }
+}
+
+impl Display for Val {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match self {
+      Val::Var(v) => write!(f, "{}", v),
+      Val::Int(i) => write!(f, "{}", i),
+    }
+  }
+}
+
+impl Display for Stmt {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    use Stmt::*;
+    match self {
+      Inp(v) => write!(f, "inp {}", v),
+      Add(v1, v2) => write!(f, "add {} {}", v1, v2),
+      Mul(v1, v2) => write!(f, "mul {} {}", v1, v2),
+      Div(v1, v2) => write!(f, "div {} {}", v1, v2),
+      Mod(v1, v2) => write!(f, "mod {} {}", v1, v2),
+      Eql(v1, v2) => write!(f, "eql {} {}", v1, v2),
+    }
+  }
+}
+
+const VARS: [(char, Var); 4] = [('w', Var::W), ('x', Var::X), ('y', Var::Y), ('z', Var::Z)];
+
 fn parse_var(input: &str) -> IResult<&str, Var> {
-  use Var::*;
-  alt((
-    pmap(tag("w"), |_| W),
-    pmap(tag("x"), |_| X),
-    pmap(tag("y"), |_| Y),
-    pmap(tag("z"), |_| Z),
-  ))(input)
+  char_map(&VARS)(input)
 }
 
 fn parse_val(input: &str) -> IResult<&str, Val> {
-  alt((pmap(parse_var, Val::Var), pmap(i32, Val::Int)))(input)
+  alt((pmap(parse_var, Val::Var), pmap(int, Val::Int)))(input)
 }
 
 fn parse_stmt(input: &str) -> IResult<&str, Stmt> {
@@ -77,9 +108,20 @@ fn parse_stmt(input: &str) -> IResult<&str, Stmt> {
   ))(input)
 }
 
-fn split_groups(input: &[Stmt]) -> Vec<Vec<Stmt>> {
+// The blocks of the program that each read a digit. A model number has 14 digits, so the program
+// has to start with the first of 14 `inp` statements.
+fn split_groups(input: &[Stmt]) -> Result<Vec<Vec<Stmt>>, NoSolution> {
+  let inputs = input.iter().filter(|s| matches!(s, Stmt::Inp(_))).count();
+  match input.first() {
+    Some(Stmt::Inp(_)) if inputs == 14 => {}
+    Some(Stmt::Inp(_)) => {
+      return Err(NoSolution(format!("the program reads {} digits instead of 14", inputs)))
+    }
+    Some(_) => return Err(NoSolution(String::from("the program does not start with inp"))),
+    None => return Err(NoSolution(String::from("the program is empty"))),
+  }
   let mut res = vec![];
-  let mut cur = vec![input[0].clone()];
+  let mut cur = vec![input[0]];
   for s in input.iter().skip(1) {
     match s {
       Stmt::Inp(_) => {
@@ -94,7 +136,7 @@ fn split_groups(input: &[Stmt]) -> Vec<Vec<Stmt>> {
 
   res.push(cur);
 
-  res
+  Ok(res)
 }
 
 fn get(memory: &HashMap<Var, i32>, val: &Val) -> i32 {
@@ -105,7 +147,9 @@ fn get(memory: &HashMap<Var, i32>, val: &Val) -> i32 {
   }
 }
 
-fn run_single_inp(program: &[Stmt], inp: i32, memory: &mut HashMap<Var, i32>) {
+// Runs a block with the digit as input. None if the ALU would crash, by dividing by 0 or taking a
+// modulo with a negative number or a divisor of at most 0, or if a value does not fit in an i32.
+fn run_single_inp(program: &[Stmt], inp: i32, memory: &mut HashMap<Var, i32>) -> Option<()> {
   use Stmt::*;
   for s in program {
     match s {
@@ -113,32 +157,76 @@ fn run_single_inp(program: &[Stmt], inp: i32, memory: &mut HashMap<Var, i32>) {
         memory.insert(*v, inp);
       }
       Add(v1, v2) => {
-        memory.insert(*v1, memory[v1] + get(&memory, v2));
+        memory.insert(*v1, memory[v1].checked_add(get(memory, v2))?);
       }
       Mul(v1, v2) => {
-        memory.insert(*v1, memory[v1] * get(&memory, v2));
+        memory.insert(*v1, memory[v1].checked_mul(get(memory, v2))?);
       }
       Div(v1, v2) => {
-        memory.insert(*v1, memory[v1] / get(&memory, v2));
+        memory.insert(*v1, memory[v1].checked_div(get(memory, v2))?);
       }
       Mod(v1, v2) => {
-        memory.insert(*v1, memory[v1] % get(&memory, v2));
+        let (a, b) = (memory[v1], get(memory, v2));
+        if a < 0 || b <= 0 {
+          return None;
+        }
+        memory.insert(*v1, a % b);
       }
       Eql(v1, v2) => {
-        memory.insert(*v1, if memory[v1] == get(&memory, v2) { 1 } else { 0 });
+        memory.insert(*v1, if memory[v1] == get(memory, v2) { 1 } else { 0 });
       }
     }
   }
+  Some(())
+}
+
+// The div, check and offset of a group shaped like the groups of the puzzle, which `generate_block`
+// writes
+fn group_constants(group: &[Stmt]) -> Option<(i32, i32, i32)> {
+  let (div, check, offset) = match (group.get(4), group.get(5), group.get(15)) {
+    (
+      Some(Stmt::Div(Var::Z, Val::Int(div))),
+      Some(Stmt::Add(Var::X, Val::Int(check))),
+      Some(Stmt::Add(Var::Y, Val::Int(offset))),
+    ) => (*div, *check, *offset),
+    _ => return None,
+  };
+  let (_, shape) = Day24::parse(&generate_block(div, check, offset)).ok()?;
+  (shape == group).then_some((div, check, offset))
+}
+
+// A group of the puzzle's shape with a positive `div z` argument and an offset of at least -1 never
+// makes z smaller than z divided by that argument, so once z reaches the product of those of the
+// remaining groups it can never get back to 0. Other programs are searched without a bound.
+fn max_z(programs: &[Vec<Stmt>]) -> Vec<i64> {
+  let mut res = vec![i64::MAX; programs.len() + 1];
+  let constants: Option<Vec<_>> = programs.iter().map(|p| group_constants(p)).collect();
+  let Some(constants) = constants else {
+    return res;
+  };
+  if constants.iter().any(|&(div, _, offset)| div < 1 || offset < -1) {
+    return res;
+  }
+  res[programs.len()] = 1;
+  for (i, (div, _, _)) in constants.iter().enumerate().rev() {
+    res[i] = res[i + 1].saturating_mul(*div as i64);
+  }
+  res
 }
 
 fn step4<I: Iterator<Item = u64> + Clone>(
-  memo: &mut [HashSet<i32>; 14],
+  memo: &mut [HashSet<i32>],
+  max_z: &[i64],
   z: i32,
   programs: &[Vec<Stmt>],
   i: usize,
   range: I,
 ) -> Option<Vec<u64>> {
+  if z as i64 >= max_z[i] {
+    return None;
+  }
   if memo[i].contains(&z) {
+    trace!("Memo hit for digit {} with z = {}", i, z);
     return None;
   }
   memo[i].insert(z);
@@ -150,17 +238,19 @@ fn step4<I: Iterator<Item = u64> + Clone>(
     memory.insert(Var::Y, 0);
     memory.insert(Var::Z, z);
 
-    run_single_inp(&programs[i], d as i32, &mut memory);
+    if run_single_inp(&programs[i], d as i32, &mut memory).is_none() {
+      continue;
+    }
     let new_z = memory[&Var::Z];
 
-    if i == 13 {
+    if i == programs.len() - 1 {
       if new_z == 0 {
         return Some(vec![d]);
       } else {
         continue;
       }
     } else {
-      match step4(memo, new_z, programs, i + 1, range.clone()) {
+      match step4(memo, max_z, new_z, programs, i + 1, range.clone()) {
         Some(ds) => {
           let mut res = ds;
           res.insert(0, d);
@@ -176,51 +266,126 @@ fn step4<I: Iterator<Item = u64> + Clone>(
   None
 }
 
-fn run<I: Iterator<Item = u64> + Clone>(program: &[Stmt], range: I) -> u64 {
-  let mut memo = [
-    HashSet::new(),
-    HashSet::new(),
-    HashSet::new(),
-    HashSet::new(),
-    HashSet::new(),
-    HashSet::new(),
-    HashSet::new(),
-    HashSet::new(),
-    HashSet::new(),
-    HashSet::new(),
-    HashSet::new(),
-    HashSet::new(),
-    HashSet::new(),
-    HashSet::new(),
-  ];
-
-  let programs = split_groups(program);
-  let digits = step4(&mut memo, 0, &programs, 0, range.clone()).unwrap();
+fn run<I: Iterator<Item = u64> + Clone>(program: &[Stmt], range: I) -> Result<u64, NoSolution> {
+  let programs = split_groups(program)?;
+  let mut memo = vec![HashSet::new(); programs.len()];
+  let max_z = max_z(&programs);
+  let digits = step4(&mut memo, &max_z, 0, &programs, 0, range.clone())
+    .ok_or_else(|| NoSolution(String::from("no model number is valid")))?;
   let mut res = 0;
   for d in digits {
     res = res * 10 + d;
   }
-  res
+  Ok(res)
 }
 
 pub struct Day24;
 
+fn generate_block(div: i32, check: i32, offset: i32) -> String {
+  format!(
+    "inp w\nmul x 0\nadd x z\nmod x 26\ndiv z {}\nadd x {}\neql x w\neql x 0\nmul y 0\nadd y 25\n\
+     mul y x\nadd y 1\nmul z y\nmul y 0\nadd y w\nadd y {}\nmul y x\nadd z y",
+    div, check, offset
+  )
+}
+
 impl Day for Day24 {
   type Input = Vec<Stmt>;
 
   fn parse(input: &str) -> IResult<&str, Self::Input> {
-    separated_list0(newline, parse_stmt)(input)
+    lines(parse_stmt)(input)
   }
 
   type Output1 = u64;
 
-  fn part_1(input: &Self::Input) -> Self::Output1 {
+  fn part_1(input: &Self::Input) -> Result<Self::Output1, NoSolution> {
     run(input, (1..=9).rev())
   }
 
   type Output2 = u64;
 
-  fn part_2(input: &Self::Input) -> Self::Output2 {
+  fn part_2(input: &Self::Input) -> Result<Self::Output2, NoSolution> {
     run(input, 1..=9)
   }
 }
+
+impl Generate for Day24 {
+  // The program has a fixed size, so the scale is ignored
+  fn generate<R: Rng>(rng: &mut R, _scale: usize) -> String {
+    // Like the real programs, z is used as a stack of base 26 numbers. Seven blocks always push
+    // `w + offset`, and seven blocks pop a value and only push when it does not match their `w`.
+    // Every popping block checks for a difference of at most 8, so a solution always exists. The
+    // stack is at most four values deep, which keeps z small and the search fast.
+    let mut blocks = vec![];
+    let mut stack = vec![];
+    let mut pushes = 7;
+    for _ in 0..14 {
+      if pushes > 0 && (stack.is_empty() || stack.len() < 4 && rng.gen_bool(0.5)) {
+        let offset = rng.gen_range(1..=16);
+        stack.push(offset);
+        pushes -= 1;
+        blocks.push(generate_block(1, rng.gen_range(10..=15), offset));
+      } else {
+        let pushed_offset = stack.pop().unwrap();
+        let check = rng.gen_range(-8..=8) - pushed_offset;
+        blocks.push(generate_block(26, check, rng.gen_range(1..=16)));
+      }
+    }
+    blocks.join("\n")
+  }
+}
+
+impl Print for Day24 {
+  fn print(input: &Self::Input) -> String {
+    print_list(input, "\n")
+  }
+}
+
+impl Explore for Day24 {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rand::SeedableRng;
+  use rand_chacha::ChaCha8Rng;
+
+  fn program(text: &str) -> Vec<Stmt> {
+    Day24::parse(text).unwrap().1
+  }
+
+  #[test]
+  fn needs_14_digits() {
+    assert!(Day24::part_1(&vec![]).is_err());
+    assert!(Day24::part_1(&program("inp w\nadd z w")).is_err());
+    assert!(Day24::part_1(&program("add z 1\ninp w")).is_err());
+  }
+
+  #[test]
+  fn skips_digits_that_crash_the_alu() {
+    let crashing = vec!["inp w\nmod w 0"; 14].join("\n");
+    assert!(Day24::part_2(&program(&crashing)).is_err());
+    // Only the digit 1 divides z by 0, so every other digit is accepted
+    let block = "inp w\nadd x w\nadd x -1\ndiv z x\nmul x 0";
+    let program = program(&vec![block; 14].join("\n"));
+    assert_eq!(Day24::part_2(&program), Ok(22222222222222));
+  }
+
+  #[test]
+  fn searches_programs_of_another_shape_without_a_bound() {
+    // z grows in every group and is only reset by the last one, so every model number is valid
+    let mut text = vec!["inp w\nadd z 5"; 13].join("\n");
+    text.push_str("\ninp w\nmul z 0");
+    let program = program(&text);
+    assert_eq!(Day24::part_1(&program), Ok(99999999999999));
+    assert_eq!(Day24::part_2(&program), Ok(11111111111111));
+  }
+
+  #[test]
+  fn bounds_programs_of_the_puzzle_shape() {
+    let program = program(&Day24::generate(&mut ChaCha8Rng::seed_from_u64(24), 1));
+    let groups = split_groups(&program).unwrap();
+    assert!(groups.iter().all(|g| group_constants(g).is_some()));
+    assert!(max_z(&groups).iter().all(|&z| z < i64::MAX));
+    assert!(Day24::part_1(&program).is_ok());
+  }
+}
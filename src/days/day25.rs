@@ -1,10 +1,10 @@
-use crate::parser::grid;
-use nom::branch::alt;
-use nom::bytes::complete::tag;
-use nom::combinator::map as pmap;
+use crate::parser::{char_map, grid, Grid};
 use nom::IResult;
+use rand::Rng;
+use std::fmt::{self, Display, Formatter};
+use std::iter::successors;
 
-use crate::days::Day;
+use crate::days::{Day, Explore, Frame, Generate, NoSolution, Print, Visualize};
 
 pub struct Day25;
 
@@ -30,52 +30,78 @@ impl Tile {
 }
 use Tile::*;
 
+impl Display for Tile {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    let c = match self {
+      South => 'v',
+      East => '>',
+      Empty => '.',
+    };
+    write!(f, "{}", c)
+  }
+}
+
+const TILES: [(char, Tile); 3] = [('v', South), ('>', East), ('.', Empty)];
+
 fn parse_tile(input: &str) -> IResult<&str, Tile> {
-  alt((
-    pmap(tag("v"), |_| South),
-    pmap(tag(">"), |_| East),
-    pmap(tag("."), |_| Empty),
-  ))(input)
+  char_map(&TILES)(input)
 }
 
-fn run(input: Vec<Vec<Tile>>, i: usize) -> usize {
+// Moves all sea cucumbers once, or returns None if none of them could move
+fn step(input: &Grid<Tile>) -> Option<Grid<Tile>> {
   let mut moved = false;
   let mut output = input.clone();
-  let height = input.len();
-  let width = input[0].len();
-  for y in 0..height {
-    for x in 0..width {
-      if input[y][x].is_east() {
-        if input[y][(x + 1) % width].is_empty() {
-          output[y][(x + 1) % width] = East;
-          output[y][x] = Empty;
-          moved = true;
-        }
-      }
+  for pos in input.positions() {
+    let target = input.wrapping_offset(pos, (1, 0));
+    if input[pos].is_east() && input[target].is_empty() {
+      output[target] = East;
+      output[pos] = Empty;
+      moved = true;
     }
   }
-  for y in 0..height {
-    for x in 0..width {
-      if input[y][x].is_south() {
-        let y_target = (y + 1) % height;
-        if output[y_target][x].is_empty() && !input[y_target][x].is_south() {
-          output[y_target][x] = South;
-          output[y][x] = Empty;
-          moved = true;
-        }
+  for pos in input.positions() {
+    if input[pos].is_south() {
+      let target = input.wrapping_offset(pos, (0, 1));
+      if output[target].is_empty() && !input[target].is_south() {
+        output[target] = South;
+        output[pos] = Empty;
+        moved = true;
       }
     }
   }
 
   if moved {
-    run(output, i + 1)
+    Some(output)
   } else {
-    i
+    None
   }
 }
 
+fn run(input: Grid<Tile>, i: usize) -> usize {
+  match step(&input) {
+    Some(output) => run(output, i + 1),
+    None => i,
+  }
+}
+
+fn to_frame(input: &Grid<Tile>) -> Frame {
+  input
+    .rows()
+    .map(|row| {
+      row
+        .iter()
+        .map(|t| match t {
+          East => ('>', Some((220, 80, 60))),
+          South => ('v', Some((60, 180, 90))),
+          Empty => ('.', None),
+        })
+        .collect()
+    })
+    .collect()
+}
+
 impl Day for Day25 {
-  type Input = Vec<Vec<Tile>>;
+  type Input = Grid<Tile>;
 
   fn parse(input: &str) -> IResult<&str, Self::Input> {
     grid(parse_tile)(input)
@@ -83,13 +109,45 @@ impl Day for Day25 {
 
   type Output1 = usize;
 
-  fn part_1(input: &Self::Input) -> Self::Output1 {
-    run(input.clone(), 1)
+  fn part_1(input: &Self::Input) -> Result<Self::Output1, NoSolution> {
+    Ok(run(input.clone(), 1))
   }
 
   type Output2 = String;
 
-  fn part_2(_input: &Self::Input) -> Self::Output2 {
-    String::from("Merry christmas")
+  fn part_2(_input: &Self::Input) -> Result<Self::Output2, NoSolution> {
+    Ok(String::from("Merry christmas"))
   }
 }
+
+impl Visualize for Day25 {
+  // Every step until the sea cucumbers stop moving
+  fn frames(input: &Self::Input) -> Box<dyn Iterator<Item = Frame> + '_> {
+    Box::new(successors(Some(input.clone()), step).map(|s| to_frame(&s)))
+  }
+}
+
+impl Generate for Day25 {
+  fn generate<R: Rng>(rng: &mut R, scale: usize) -> String {
+    (0..137 * scale)
+      .map(|_| {
+        (0..139 * scale)
+          .map(|_| match rng.gen_range(0..4) {
+            0 => '>',
+            1 => 'v',
+            _ => '.',
+          })
+          .collect::<String>()
+      })
+      .collect::<Vec<String>>()
+      .join("\n")
+  }
+}
+
+impl Print for Day25 {
+  fn print(input: &Self::Input) -> String {
+    input.to_string()
+  }
+}
+
+impl Explore for Day25 {}
@@ -1,8 +1,10 @@
+use crate::input;
 use crate::parser::MyErr;
-use crate::Instant;
 use nom::IResult;
-use std::fmt::Display;
-use std::fs::read_to_string;
+use rand::Rng;
+use std::borrow::Cow;
+use std::fmt::{self, Display, Formatter};
+use std::time::{Duration, Instant};
 
 pub mod day01;
 pub mod day02;
@@ -30,6 +32,178 @@ pub mod day23;
 pub mod day24;
 pub mod day25;
 
+// Evaluates `$body` with `$d` as the `Day` implementation of the given day number.
+// Panics if you provide a value outside the range of 1 to 25
+#[macro_export]
+macro_rules! with_day {
+  ($day:expr, $d:ident => $body:expr) => {
+    match $day {
+      1 => {
+        type $d = $crate::days::day01::Day01;
+        $body
+      }
+      2 => {
+        type $d = $crate::days::day02::Day02;
+        $body
+      }
+      3 => {
+        type $d = $crate::days::day03::Day03;
+        $body
+      }
+      4 => {
+        type $d = $crate::days::day04::Day04;
+        $body
+      }
+      5 => {
+        type $d = $crate::days::day05::Day05;
+        $body
+      }
+      6 => {
+        type $d = $crate::days::day06::Day06;
+        $body
+      }
+      7 => {
+        type $d = $crate::days::day07::Day07;
+        $body
+      }
+      8 => {
+        type $d = $crate::days::day08::Day08;
+        $body
+      }
+      9 => {
+        type $d = $crate::days::day09::Day09;
+        $body
+      }
+      10 => {
+        type $d = $crate::days::day10::Day10;
+        $body
+      }
+      11 => {
+        type $d = $crate::days::day11::Day11;
+        $body
+      }
+      12 => {
+        type $d = $crate::days::day12::Day12;
+        $body
+      }
+      13 => {
+        type $d = $crate::days::day13::Day13;
+        $body
+      }
+      14 => {
+        type $d = $crate::days::day14::Day14;
+        $body
+      }
+      15 => {
+        type $d = $crate::days::day15::Day15;
+        $body
+      }
+      16 => {
+        type $d = $crate::days::day16::Day16;
+        $body
+      }
+      17 => {
+        type $d = $crate::days::day17::Day17;
+        $body
+      }
+      18 => {
+        type $d = $crate::days::day18::Day18;
+        $body
+      }
+      19 => {
+        type $d = $crate::days::day19::Day19;
+        $body
+      }
+      20 => {
+        type $d = $crate::days::day20::Day20;
+        $body
+      }
+      21 => {
+        type $d = $crate::days::day21::Day21;
+        $body
+      }
+      22 => {
+        type $d = $crate::days::day22::Day22;
+        $body
+      }
+      23 => {
+        type $d = $crate::days::day23::Day23;
+        $body
+      }
+      24 => {
+        type $d = $crate::days::day24::Day24;
+        $body
+      }
+      25 => {
+        type $d = $crate::days::day25::Day25;
+        $body
+      }
+      d => panic!("Provided unsupported day {}", d),
+    }
+  };
+}
+
+pub const TITLES: [&str; 25] = [
+  "Sonar Sweep",
+  "Dive!",
+  "Binary Diagnostic",
+  "Giant Squid",
+  "Hydrothermal Venture",
+  "Lanternfish",
+  "The Treachery of Whales",
+  "Seven Segment Search",
+  "Smoke Basin",
+  "Syntax Scoring",
+  "Dumbo Octopus",
+  "Passage Pathing",
+  "Transparent Origami",
+  "Extended Polymerization",
+  "Chiton",
+  "Packet Decoder",
+  "Trick Shot",
+  "Snailfish",
+  "Beacon Scanner",
+  "Trench Map",
+  "Dirac Dice",
+  "Reactor Reboot",
+  "Amphipod",
+  "Arithmetic Logic Unit",
+  "Sea Cucumber",
+];
+
+#[derive(Debug, Clone, Copy)]
+pub struct Timings {
+  pub part_1: Duration,
+  pub part_2: Duration,
+}
+
+// Returned by a part when its input has no answer, with the reason why
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoSolution(pub String);
+
+impl Display for NoSolution {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    write!(f, "no solution, {}", self.0)
+  }
+}
+
+// The answers of both parts, and how long each took
+#[derive(Debug, Clone)]
+pub struct Answers {
+  pub part_1: Result<String, NoSolution>,
+  pub part_2: Result<String, NoSolution>,
+  pub timings: Timings,
+}
+
+// Prints the answer of a part, or the reason it has none
+fn print_part<T: Display>(part: usize, answer: &Result<T, NoSolution>, duration: Duration) {
+  match answer {
+    Ok(answer) => println!("Part {}: {}", part, answer),
+    Err(e) => println!("Part {}: {}", part, e),
+  }
+  println!("Part {} took {}s", part, duration.as_secs_f32());
+}
+
 pub trait Day {
   type Input;
 
@@ -37,29 +211,96 @@ pub trait Day {
 
   type Output1: Display;
 
-  fn part_1(input: &Self::Input) -> Self::Output1;
+  fn part_1(input: &Self::Input) -> Result<Self::Output1, NoSolution>;
 
   type Output2: Display;
 
-  fn part_2(input: &Self::Input) -> Self::Output2;
+  fn part_2(input: &Self::Input) -> Result<Self::Output2, NoSolution>;
 
   fn parse_file(fp: &str) -> Result<Self::Input, MyErr> {
-    let input_string = read_to_string(fp)?;
+    let input_string = input::read(fp)?;
     let (_, input) = Self::parse(&input_string)?;
     Ok(input)
   }
 
-  fn run_day(fp: &str) {
+  fn solve(input: &Self::Input) -> Answers {
+    let before1 = Instant::now();
+    let answer_1 = Self::part_1(input);
+    let part_1 = before1.elapsed();
+    let before2 = Instant::now();
+    let answer_2 = Self::part_2(input);
+    let part_2 = before2.elapsed();
+    Answers {
+      part_1: answer_1.map(|a| a.to_string()),
+      part_2: answer_2.map(|a| a.to_string()),
+      timings: Timings { part_1, part_2 },
+    }
+  }
+
+  // Returns the timings if both parts have an answer
+  fn run_day(fp: &str) -> Option<Timings> {
     match Self::parse_file(fp) {
-      Err(e) => println!("{:?}", e),
+      Err(e) => {
+        println!("{:?}", e);
+        None
+      }
       Ok(input) => {
-        let before1 = Instant::now();
-        println!("Part 1: {}", Self::part_1(&input));
-        println!("Part 1 took {}s", before1.elapsed().as_secs_f32());
-        let before2 = Instant::now();
-        println!("Part 2: {}", Self::part_2(&input));
-        println!("Part 2 took {}s", before2.elapsed().as_secs_f32());
+        let answers = Self::solve(&input);
+        print_part(1, &answers.part_1, answers.timings.part_1);
+        print_part(2, &answers.part_2, answers.timings.part_2);
+        match answers {
+          Answers {
+            part_1: Ok(_),
+            part_2: Ok(_),
+            timings,
+          } => Some(timings),
+          _ => None,
+        }
       }
     }
   }
 }
+
+pub type Colour = (u8, u8, u8);
+
+// A character grid, where every cell can optionally have its own colour
+pub type Frame = Vec<Vec<(char, Option<Colour>)>>;
+
+pub trait Visualize: Day {
+  fn frames(input: &Self::Input) -> Box<dyn Iterator<Item = Frame> + '_>;
+}
+
+pub trait Generate: Day {
+  // Returns a random input that `Day::parse` accepts. A scale of 1 results in an input of about the
+  // size of a real one, higher scales make it proportionally larger.
+  fn generate<R: Rng>(rng: &mut R, scale: usize) -> String;
+}
+
+pub trait Print: Day {
+  // Writes the input back as puzzle text in canonical form, so that parsing the result gives an
+  // equal input
+  fn print(input: &Self::Input) -> String;
+}
+
+// A number the puzzle fixes, with the value it has in each part
+#[derive(Debug, Clone)]
+pub struct Param {
+  pub name: Cow<'static, str>,
+  pub part_1: usize,
+  pub part_2: usize,
+}
+
+pub trait Explore: Day {
+  // The numbers worth experimenting with, if the puzzle has any
+  const PARAMS: &'static [Param] = &[];
+
+  // The parameters of this input, days whose parameters depend on the input override this
+  fn params(_input: &Self::Input) -> Vec<Param> {
+    Self::PARAMS.to_vec()
+  }
+
+  // Solves the puzzle with the given values of `params`, in the same order
+  fn run_with(_input: &Self::Input, _values: &[usize]) -> Result<String, NoSolution> {
+    Err(NoSolution(String::from("this day has no parameters")))
+  }
+}
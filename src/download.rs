@@ -0,0 +1,218 @@
+use crate::input::{self, normalise};
+use crate::parser::MyErr;
+use crate::YEAR;
+use chrono::prelude::*;
+use log::{debug, info, warn};
+use reqwest::blocking::{Client, Response};
+use reqwest::header::RETRY_AFTER;
+use reqwest::StatusCode;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+const USER_AGENT: &str = concat!(
+  env!("CARGO_PKG_NAME"),
+  "/",
+  env!("CARGO_PKG_VERSION"),
+  " (input downloader, caches inputs and waits between requests)"
+);
+// The minimum time between two requests to adventofcode.com
+const THROTTLE: Duration = Duration::from_secs(3);
+// Retries after a 429 or 5xx response wait this long, doubling every time
+const BACKOFF: Duration = Duration::from_secs(10);
+const MAX_RETRIES: u32 = 4;
+
+#[derive(Debug)]
+pub enum DownloadErr {
+  NoSession(std::io::Error),
+  Locked(DateTime<Local>),
+  Request(reqwest::Error),
+  Status(StatusCode),
+  FileError(MyErr),
+}
+
+impl Display for DownloadErr {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match self {
+      DownloadErr::NoSession(e) => write!(f, "could not read the .session file ({})", e),
+      DownloadErr::Locked(unlock) => write!(f, "the puzzle unlocks at {}", unlock),
+      DownloadErr::Request(e) => write!(f, "the request failed ({})", e),
+      DownloadErr::Status(StatusCode::BAD_REQUEST) => write!(
+        f,
+        "the server refused the request. Is your correct session cookie in your .session file?"
+      ),
+      DownloadErr::Status(status) => write!(f, "the server responded with {}", status),
+      DownloadErr::FileError(e) => write!(f, "could not write the input ({:?})", e),
+    }
+  }
+}
+
+impl From<reqwest::Error> for DownloadErr {
+  fn from(e: reqwest::Error) -> DownloadErr {
+    DownloadErr::Request(e)
+  }
+}
+
+pub enum Outcome {
+  Downloaded(String),
+  Cached(String),
+}
+
+// Puzzles unlock at midnight US-Eastern time, which is UTC-5 during all of December
+pub fn unlock_time(day: usize) -> DateTime<Utc> {
+  let eastern = FixedOffset::west_opt(5 * 3600).unwrap();
+  eastern
+    .ymd(YEAR as i32, 12, day as u32)
+    .and_hms(0, 0, 0)
+    .with_timezone(&Utc)
+}
+
+pub fn is_unlocked(day: usize) -> bool {
+  Utc::now() >= unlock_time(day)
+}
+
+pub fn input_path(day: usize) -> String {
+  format!("inputs/day{:02}.txt", day)
+}
+
+pub struct Downloader {
+  client: Client,
+  session: String,
+  force: bool,
+  last_request: Option<Instant>,
+}
+
+impl Downloader {
+  // Existing inputs are only downloaded again if `force` is set
+  pub fn new(force: bool) -> Result<Downloader, DownloadErr> {
+    let session = fs::read_to_string(".session").map_err(DownloadErr::NoSession)?;
+    let client = Client::builder().user_agent(USER_AGENT).build()?;
+    Ok(Downloader {
+      client,
+      session: session.trim().to_owned(),
+      force,
+      last_request: None,
+    })
+  }
+
+  // Waits until at least `THROTTLE` has passed since the previous request
+  fn throttle(&mut self) {
+    if let Some(last) = self.last_request {
+      let elapsed = last.elapsed();
+      if elapsed < THROTTLE {
+        debug!("Waiting {:?} before the next request", THROTTLE - elapsed);
+        sleep(THROTTLE - elapsed);
+      }
+    }
+    self.last_request = Some(Instant::now());
+  }
+
+  fn get(&mut self, url: &str) -> Result<Response, DownloadErr> {
+    let mut backoff = BACKOFF;
+    let mut retries = 0;
+    loop {
+      self.throttle();
+      let response = self
+        .client
+        .get(url)
+        .header("cookie", format!("session={};", self.session))
+        .send()?;
+      let status = response.status();
+      if status.is_success() {
+        return Ok(response);
+      }
+      let is_transient = status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
+      if !is_transient || retries >= MAX_RETRIES {
+        return Err(DownloadErr::Status(status));
+      }
+      // Respect the delay the server asks for, if it is given in seconds
+      let wait = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .map_or(backoff, Duration::from_secs);
+      warn!("The server responded with {}, retrying in {:?}", status, wait);
+      sleep(wait);
+      backoff *= 2;
+      retries += 1;
+    }
+  }
+
+  // The number of stars of every day, read from the calendar on the page of the year
+  pub fn stars(&mut self) -> Result<Vec<usize>, DownloadErr> {
+    let url = format!("https://adventofcode.com/{}", YEAR);
+    info!("Downloading {}", url);
+    let page = self.get(&url)?.text()?;
+    let stars = (1..=25)
+      .map(|day| {
+        if page.contains(&format!("aria-label=\"Day {}, two stars\"", day)) {
+          2
+        } else if page.contains(&format!("aria-label=\"Day {}, one star\"", day)) {
+          1
+        } else {
+          0
+        }
+      })
+      .collect();
+    Ok(stars)
+  }
+
+  pub fn download(&mut self, day: usize) -> Result<Outcome, DownloadErr> {
+    let path = input_path(day);
+    if !self.force && input::exists(&path) {
+      return Ok(Outcome::Cached(path));
+    }
+    if !is_unlocked(day) {
+      return Err(DownloadErr::Locked(unlock_time(day).with_timezone(&Local)));
+    }
+    let url = format!("https://adventofcode.com/{}/day/{}/input", YEAR, day);
+    info!("Downloading {}", url);
+    let (text, changes) = normalise(&self.get(&url)?.text()?);
+    for change in changes {
+      info!("Day {}: {}", day, change);
+    }
+    fs::create_dir_all("inputs").map_err(|e| DownloadErr::FileError(e.into()))?;
+    let path = input::write(&path, &text).map_err(DownloadErr::FileError)?;
+    Ok(Outcome::Downloaded(path))
+  }
+}
+
+// Prints the outcome of a download, and returns whether it succeeded
+pub fn report(day: usize, result: Result<Outcome, DownloadErr>) -> bool {
+  match result {
+    Ok(Outcome::Downloaded(path)) => println!("Successfully downloaded input to {}", path),
+    Ok(Outcome::Cached(path)) => {
+      println!("{} already exists, use --force to download it again", path)
+    }
+    Err(e) => {
+      println!("Could not get input for day {}: {}", day, e);
+      return false;
+    }
+  }
+  true
+}
+
+// Downloads the inputs of all unlocked days, stopping at the first failure
+pub fn download_all(force: bool) -> Result<(), DownloadErr> {
+  let mut downloader = Downloader::new(force)?;
+  for day in (1..=25).filter(|day| is_unlocked(*day)) {
+    if !report(day, downloader.download(day)) {
+      break;
+    }
+  }
+  if !is_unlocked(25) {
+    let next = (1..=25).find(|day| !is_unlocked(*day)).unwrap();
+    println!("Day {} unlocks at {}", next, unlock_time(next).with_timezone(&Local));
+  }
+  Ok(())
+}
+
+// Downloads the input of one day, returning the error if that fails
+pub fn download(day: usize, force: bool) -> Result<(), DownloadErr> {
+  let mut downloader = Downloader::new(force)?;
+  let outcome = downloader.download(day)?;
+  report(day, Ok(outcome));
+  Ok(())
+}
@@ -0,0 +1,113 @@
+use crate::days::{Explore, NoSolution, Param};
+use crate::parser::MyErr;
+use std::fmt::{Debug, Display};
+use std::io::{stdin, stdout, BufRead, Write};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::time::Instant;
+
+const HELP: &str = "Commands:
+  part1              run part 1
+  part2              run part 2
+  input              print the parsed input
+  params             list the parameters of this day and their current values
+  set <name> <value> change a parameter
+  run                solve with the current parameter values
+  help               show this message
+  quit               leave
+Every command prints how long it took.";
+
+fn print_answer<T: Display>(answer: Result<T, NoSolution>) {
+  match answer {
+    Ok(answer) => println!("{}", answer),
+    Err(e) => println!("{}", e),
+  }
+}
+
+fn print_params(params: &[Param], values: &[usize]) {
+  if params.is_empty() {
+    println!("This day has no parameters");
+  }
+  for (param, value) in params.iter().zip(values) {
+    println!(
+      "{} = {} (part 1 uses {}, part 2 uses {})",
+      param.name, value, param.part_1, param.part_2
+    );
+  }
+}
+
+fn set_param(params: &[Param], values: &mut [usize], args: &[&str]) {
+  let (name, value) = match args {
+    [name, value] => (name, value),
+    _ => {
+      println!("Usage: set <name> <value>");
+      return;
+    }
+  };
+  let i = match params.iter().position(|p| p.name == *name) {
+    Some(i) => i,
+    None => {
+      println!("{} is not a parameter of this day", name);
+      return;
+    }
+  };
+  match value.parse() {
+    Ok(value) => values[i] = value,
+    Err(_) => println!("{} is not a valid {}. Please provide a number.", value, name),
+  }
+}
+
+// Runs a single command, and returns false when the session should end
+fn execute<E: Explore>(
+  input: &E::Input,
+  params: &[Param],
+  values: &mut [usize],
+  line: &str,
+) -> bool
+where
+  E::Input: Debug,
+{
+  let words: Vec<&str> = line.split_whitespace().collect();
+  match words[..] {
+    [] => {}
+    ["quit"] | ["exit"] => return false,
+    ["help"] => println!("{}", HELP),
+    ["part1"] => print_answer(E::part_1(input)),
+    ["part2"] => print_answer(E::part_2(input)),
+    ["input"] => println!("{:?}", input),
+    ["params"] => print_params(params, values),
+    ["set", ref args @ ..] => set_param(params, values, args),
+    ["run"] => print_answer(E::run_with(input, values)),
+    _ => println!("Unknown command {}, type help to see the commands", line.trim()),
+  }
+  true
+}
+
+// Parses the input once, then reads commands from stdin until it ends or the user quits
+pub fn explore<E: Explore>(fp: &str, day: usize) -> Result<(), MyErr>
+where
+  E::Input: Debug,
+{
+  let input = E::parse_file(fp)?;
+  let params = E::params(&input);
+  let mut values: Vec<usize> = params.iter().map(|p| p.part_1).collect();
+  println!("Parsed the input of day {}. Type help to see the commands.", day);
+
+  let mut lines = stdin().lock().lines();
+  loop {
+    print!("day{:02}> ", day);
+    stdout().flush()?;
+    let line = match lines.next() {
+      Some(line) => line?,
+      None => break,
+    };
+    let before = Instant::now();
+    // A panicking part should not end the session, the panic message is printed already
+    match catch_unwind(AssertUnwindSafe(|| execute::<E>(&input, &params, &mut values, &line))) {
+      Ok(true) => {}
+      Ok(false) => break,
+      Err(_) => println!("The command panicked"),
+    }
+    println!("took {}s", before.elapsed().as_secs_f32());
+  }
+  Ok(())
+}
@@ -0,0 +1,403 @@
+use std::collections::HashSet;
+use std::fmt::{self, Display, Formatter};
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+// The integer types coordinates can have
+pub trait Coord:
+  Copy + Ord + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Neg<Output = Self>
+{
+  const ZERO: Self;
+  const ONE: Self;
+
+  fn abs(self) -> Self;
+
+  fn signum(self) -> Self;
+
+  fn to_f64(self) -> f64;
+}
+
+macro_rules! impl_coord {
+  ($($t:ty),*) => {
+    $(
+      impl Coord for $t {
+        const ZERO: Self = 0;
+        const ONE: Self = 1;
+
+        fn abs(self) -> Self {
+          <$t>::abs(self)
+        }
+
+        fn signum(self) -> Self {
+          <$t>::signum(self)
+        }
+
+        fn to_f64(self) -> f64 {
+          self as f64
+        }
+      }
+    )*
+  };
+}
+
+impl_coord!(i8, i16, i32, i64, i128, isize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Vec2<T> {
+  pub x: T,
+  pub y: T,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Vec3<T> {
+  pub x: T,
+  pub y: T,
+  pub z: T,
+}
+
+impl<T: Coord> Vec2<T> {
+  pub fn new(x: T, y: T) -> Vec2<T> {
+    Vec2 { x, y }
+  }
+
+  pub fn zero() -> Vec2<T> {
+    Vec2::new(T::ZERO, T::ZERO)
+  }
+
+  // The Manhattan length, the sum of the absolute coordinates
+  pub fn manhattan(self) -> T {
+    self.x.abs() + self.y.abs()
+  }
+
+  pub fn manhattan_distance(self, other: Vec2<T>) -> T {
+    (self - other).manhattan()
+  }
+
+  // Converted to floating point before subtracting, so far apart points can not overflow
+  pub fn euclidean_distance(self, other: Vec2<T>) -> f64 {
+    let dx = self.x.to_f64() - other.x.to_f64();
+    let dy = self.y.to_f64() - other.y.to_f64();
+    (dx * dx + dy * dy).sqrt()
+  }
+
+  // The sign of every coordinate, a step along a horizontal, vertical or diagonal line
+  pub fn signum(self) -> Vec2<T> {
+    Vec2::new(self.x.signum(), self.y.signum())
+  }
+}
+
+impl<T: Coord> Vec3<T> {
+  pub fn new(x: T, y: T, z: T) -> Vec3<T> {
+    Vec3 { x, y, z }
+  }
+
+  pub fn zero() -> Vec3<T> {
+    Vec3::new(T::ZERO, T::ZERO, T::ZERO)
+  }
+
+  // A vector with the same value for every coordinate
+  pub fn splat(v: T) -> Vec3<T> {
+    Vec3::new(v, v, v)
+  }
+
+  pub fn manhattan(self) -> T {
+    self.x.abs() + self.y.abs() + self.z.abs()
+  }
+
+  pub fn manhattan_distance(self, other: Vec3<T>) -> T {
+    (self - other).manhattan()
+  }
+
+  pub fn euclidean_distance(self, other: Vec3<T>) -> f64 {
+    let dx = self.x.to_f64() - other.x.to_f64();
+    let dy = self.y.to_f64() - other.y.to_f64();
+    let dz = self.z.to_f64() - other.z.to_f64();
+    (dx * dx + dy * dy + dz * dz).sqrt()
+  }
+
+  pub fn signum(self) -> Vec3<T> {
+    Vec3::new(self.x.signum(), self.y.signum(), self.z.signum())
+  }
+
+  pub fn to_array(self) -> [T; 3] {
+    [self.x, self.y, self.z]
+  }
+}
+
+// Implements the component-wise operators, and multiplication by a scalar
+macro_rules! impl_ops {
+  ($v:ident, $($c:ident),*) => {
+    impl<T: Coord> Add for $v<T> {
+      type Output = $v<T>;
+
+      fn add(self, other: $v<T>) -> $v<T> {
+        $v { $($c: self.$c + other.$c),* }
+      }
+    }
+
+    impl<T: Coord> Sub for $v<T> {
+      type Output = $v<T>;
+
+      fn sub(self, other: $v<T>) -> $v<T> {
+        $v { $($c: self.$c - other.$c),* }
+      }
+    }
+
+    impl<T: Coord> Neg for $v<T> {
+      type Output = $v<T>;
+
+      fn neg(self) -> $v<T> {
+        $v { $($c: -self.$c),* }
+      }
+    }
+
+    impl<T: Coord> Mul<T> for $v<T> {
+      type Output = $v<T>;
+
+      fn mul(self, n: T) -> $v<T> {
+        $v { $($c: self.$c * n),* }
+      }
+    }
+
+    impl<T: Coord> AddAssign for $v<T> {
+      fn add_assign(&mut self, other: $v<T>) {
+        *self = *self + other;
+      }
+    }
+
+    impl<T: Coord> SubAssign for $v<T> {
+      fn sub_assign(&mut self, other: $v<T>) {
+        *self = *self - other;
+      }
+    }
+  };
+}
+
+impl_ops!(Vec2, x, y);
+impl_ops!(Vec3, x, y, z);
+
+// Written the way most puzzles write coordinates, "x,y"
+impl<T: Display> Display for Vec2<T> {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    write!(f, "{},{}", self.x, self.y)
+  }
+}
+
+impl<T: Display> Display for Vec3<T> {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    write!(f, "{},{},{}", self.x, self.y, self.z)
+  }
+}
+
+// An axis-aligned rectangle, including both corners. It is empty if `max` is below `min` on any
+// axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Box2<T> {
+  pub min: Vec2<T>,
+  pub max: Vec2<T>,
+}
+
+// An axis-aligned cuboid, including both corners
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Box3<T> {
+  pub min: Vec3<T>,
+  pub max: Vec3<T>,
+}
+
+impl<T: Coord> Box2<T> {
+  pub fn new(min: Vec2<T>, max: Vec2<T>) -> Box2<T> {
+    Box2 { min, max }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.max.x < self.min.x || self.max.y < self.min.y
+  }
+
+  pub fn contains(&self, p: Vec2<T>) -> bool {
+    self.min.x <= p.x && p.x <= self.max.x && self.min.y <= p.y && p.y <= self.max.y
+  }
+
+  pub fn intersection(&self, other: &Box2<T>) -> Option<Box2<T>> {
+    let res = Box2::new(
+      Vec2::new(self.min.x.max(other.min.x), self.min.y.max(other.min.y)),
+      Vec2::new(self.max.x.min(other.max.x), self.max.y.min(other.max.y)),
+    );
+    (!res.is_empty()).then_some(res)
+  }
+
+  // The number of points inside
+  pub fn area(&self) -> T {
+    if self.is_empty() {
+      T::ZERO
+    } else {
+      (self.max.x - self.min.x + T::ONE) * (self.max.y - self.min.y + T::ONE)
+    }
+  }
+}
+
+impl<T: Coord> Box3<T> {
+  pub fn new(min: Vec3<T>, max: Vec3<T>) -> Box3<T> {
+    Box3 { min, max }
+  }
+
+  // The cube of points at most `radius` away from the centre on every axis
+  pub fn around(centre: Vec3<T>, radius: T) -> Box3<T> {
+    Box3::new(centre - Vec3::splat(radius), centre + Vec3::splat(radius))
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.max.x < self.min.x || self.max.y < self.min.y || self.max.z < self.min.z
+  }
+
+  pub fn contains(&self, p: Vec3<T>) -> bool {
+    self.min.x <= p.x
+      && p.x <= self.max.x
+      && self.min.y <= p.y
+      && p.y <= self.max.y
+      && self.min.z <= p.z
+      && p.z <= self.max.z
+  }
+
+  pub fn intersection(&self, other: &Box3<T>) -> Option<Box3<T>> {
+    let res = Box3::new(
+      Vec3::new(
+        self.min.x.max(other.min.x),
+        self.min.y.max(other.min.y),
+        self.min.z.max(other.min.z),
+      ),
+      Vec3::new(
+        self.max.x.min(other.max.x),
+        self.max.y.min(other.max.y),
+        self.max.z.min(other.max.z),
+      ),
+    );
+    (!res.is_empty()).then_some(res)
+  }
+
+  // The number of points inside
+  pub fn volume(&self) -> T {
+    if self.is_empty() {
+      T::ZERO
+    } else {
+      let size = self.max - self.min + Vec3::splat(T::ONE);
+      size.x * size.y * size.z
+    }
+  }
+}
+
+// A rotation in 3D by multiples of 90 degrees, as a matrix of -1, 0 and 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rotation([[i8; 3]; 3]);
+
+impl Rotation {
+  pub const IDENTITY: Rotation = Rotation([[1, 0, 0], [0, 1, 0], [0, 0, 1]]);
+  // A quarter turn around the x axis, and around the z axis
+  const QUARTER_X: Rotation = Rotation([[1, 0, 0], [0, 0, -1], [0, 1, 0]]);
+  const QUARTER_Z: Rotation = Rotation([[0, -1, 0], [1, 0, 0], [0, 0, 1]]);
+
+  // The 24 orientations of a cube, generated by combining quarter turns until no new ones appear.
+  // The identity comes first.
+  pub fn all() -> Vec<Rotation> {
+    let mut res = vec![Rotation::IDENTITY];
+    let mut seen = HashSet::from([Rotation::IDENTITY]);
+    let mut i = 0;
+    while i < res.len() {
+      for generator in [Rotation::QUARTER_X, Rotation::QUARTER_Z] {
+        let r = generator * res[i];
+        if seen.insert(r) {
+          res.push(r);
+        }
+      }
+      i += 1;
+    }
+    res
+  }
+
+  // The rotation that undoes this one, which for a rotation matrix is its transpose
+  pub fn inverse(&self) -> Rotation {
+    let m = self.0;
+    Rotation([0, 1, 2].map(|i| [m[0][i], m[1][i], m[2][i]]))
+  }
+
+  pub fn apply<T: Coord>(&self, v: Vec3<T>) -> Vec3<T> {
+    let c = v.to_array();
+    let [x, y, z] = self.0.map(|row| {
+      row.iter().zip(c).fold(T::ZERO, |acc, (m, c)| match m {
+        1 => acc + c,
+        -1 => acc - c,
+        _ => acc,
+      })
+    });
+    Vec3::new(x, y, z)
+  }
+}
+
+// Applying `a * b` is applying `b` first, then `a`
+impl Mul for Rotation {
+  type Output = Rotation;
+
+  fn mul(self, other: Rotation) -> Rotation {
+    let (a, b) = (self.0, other.0);
+    Rotation([0, 1, 2].map(|i| [0, 1, 2].map(|j| (0..3).map(|k| a[i][k] * b[k][j]).sum())))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn cube(min: i32, max: i32) -> Box3<i32> {
+    Box3::new(Vec3::splat(min), Vec3::splat(max))
+  }
+
+  fn determinant(r: &Rotation) -> i32 {
+    let m = r.0.map(|row| row.map(|x| x as i32));
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+      - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+      + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+  }
+
+  #[test]
+  fn there_are_24_rotations() {
+    let all = Rotation::all();
+    assert_eq!(all.len(), 24);
+    assert_eq!(all.iter().collect::<HashSet<_>>().len(), 24);
+    assert_eq!(all[0], Rotation::IDENTITY);
+    assert!(all.iter().all(|r| determinant(r) == 1));
+    let v = Vec3::new(1, 2, 3);
+    assert!(all.iter().all(|r| r.inverse().apply(r.apply(v)) == v));
+    assert!(all.iter().all(|r| *r * r.inverse() == Rotation::IDENTITY));
+  }
+
+  #[test]
+  fn intersects_boxes() {
+    // Disjoint
+    assert_eq!(cube(0, 1).intersection(&cube(2, 3)), None);
+    // Touching along a face, which still shares the points of that face
+    let touching = Box3::new(Vec3::new(2, 0, 0), Vec3::new(4, 2, 2));
+    let face = Box3::new(Vec3::new(2, 0, 0), Vec3::new(2, 2, 2));
+    assert_eq!(cube(0, 2).intersection(&touching), Some(face));
+    assert_eq!(face.volume(), 9);
+    // Nested
+    assert_eq!(cube(0, 9).intersection(&cube(3, 5)), Some(cube(3, 5)));
+    assert_eq!(cube(3, 5).volume(), 27);
+    assert_eq!(cube(1, 0).volume(), 0);
+    assert!(cube(0, 2).contains(Vec3::new(2, 0, 1)) && !cube(0, 2).contains(Vec3::new(3, 0, 1)));
+    assert_eq!(Box3::around(Vec3::splat(1), 1), cube(0, 2));
+  }
+
+  #[test]
+  fn does_arithmetic_on_vectors() {
+    let (a, b) = (Vec3::new(1, -2, 3), Vec3::new(4, 5, -6));
+    assert_eq!(a + b, Vec3::new(5, 3, -3));
+    assert_eq!(a - b, Vec3::new(-3, -7, 9));
+    assert_eq!(-a, Vec3::new(-1, 2, -3));
+    assert_eq!(a * 2, Vec3::new(2, -4, 6));
+    let mut c = a;
+    c += b;
+    c -= a;
+    assert_eq!(c, b);
+    assert_eq!(a.manhattan_distance(b), 3 + 7 + 9);
+    assert_eq!(b.signum(), Vec3::new(1, 1, -1));
+    assert_eq!(Vec3::new(3, 4, 12).euclidean_distance(Vec3::zero()), 13.0);
+  }
+}
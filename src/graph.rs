@@ -0,0 +1,327 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+use std::hash::Hash;
+use std::iter::from_fn;
+use std::ops::Range;
+
+pub type NodeId = usize;
+
+// An undirected graph with a unique label and an attribute of type A on every node. Nodes are
+// numbered in the order they were added.
+#[derive(Debug, Clone)]
+pub struct Graph<A> {
+  labels: Vec<String>,
+  ids: HashMap<String, NodeId>,
+  attrs: Vec<A>,
+  adjacency: Vec<Vec<NodeId>>,
+}
+
+impl<A> Default for Graph<A> {
+  fn default() -> Graph<A> {
+    Graph {
+      labels: vec![],
+      ids: HashMap::new(),
+      attrs: vec![],
+      adjacency: vec![],
+    }
+  }
+}
+
+impl<A> Graph<A> {
+  pub fn new() -> Graph<A> {
+    Graph::default()
+  }
+
+  // The node with this label. If there is none yet, it is added with the attribute `attr` gives
+  // for the label.
+  pub fn intern<F: FnOnce(&str) -> A>(&mut self, label: &str, attr: F) -> NodeId {
+    if let Some(id) = self.ids.get(label) {
+      return *id;
+    }
+    let id = self.labels.len();
+    self.labels.push(label.to_owned());
+    self.ids.insert(label.to_owned(), id);
+    self.attrs.push(attr(label));
+    self.adjacency.push(vec![]);
+    id
+  }
+
+  // Adding an edge that exists already does nothing
+  pub fn add_edge(&mut self, a: NodeId, b: NodeId) {
+    if !self.adjacency[a].contains(&b) {
+      self.adjacency[a].push(b);
+      if a != b {
+        self.adjacency[b].push(a);
+      }
+    }
+  }
+
+  pub fn id(&self, label: &str) -> Option<NodeId> {
+    self.ids.get(label).copied()
+  }
+
+  pub fn label(&self, id: NodeId) -> &str {
+    &self.labels[id]
+  }
+
+  pub fn attr(&self, id: NodeId) -> &A {
+    &self.attrs[id]
+  }
+
+  pub fn neighbors(&self, id: NodeId) -> &[NodeId] {
+    &self.adjacency[id]
+  }
+
+  pub fn len(&self) -> usize {
+    self.labels.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.labels.is_empty()
+  }
+
+  pub fn nodes(&self) -> Range<NodeId> {
+    0..self.len()
+  }
+
+  // Every edge once, with the lowest node first
+  pub fn edges(&self) -> impl Iterator<Item = (NodeId, NodeId)> + '_ {
+    self.nodes().flat_map(move |a| {
+      self.adjacency[a]
+        .iter()
+        .filter(move |b| a <= **b)
+        .map(move |b| (a, *b))
+    })
+  }
+
+  // The labels along a path
+  pub fn path_labels(&self, path: &[NodeId]) -> Vec<&str> {
+    path.iter().map(|id| self.label(*id)).collect()
+  }
+
+  // The paths `count_paths` counts, found lazily depth first, so they can be taken one by one or
+  // up to a limit
+  pub fn paths<'a, S, F>(
+    &'a self,
+    from: NodeId,
+    to: NodeId,
+    state: S,
+    step: F,
+  ) -> impl Iterator<Item = Vec<NodeId>> + 'a
+  where
+    S: 'a,
+    F: FnMut(&S, NodeId) -> Option<S> + 'a,
+  {
+    self.walk_paths(from, to, state, step, false)
+  }
+
+  // The same paths found breadth first, so those through the fewest nodes come first. All paths of
+  // the current length are kept in memory.
+  pub fn shortest_paths<'a, S, F>(
+    &'a self,
+    from: NodeId,
+    to: NodeId,
+    state: S,
+    step: F,
+  ) -> impl Iterator<Item = Vec<NodeId>> + 'a
+  where
+    S: 'a,
+    F: FnMut(&S, NodeId) -> Option<S> + 'a,
+  {
+    self.walk_paths(from, to, state, step, true)
+  }
+
+  fn walk_paths<'a, S, F>(
+    &'a self,
+    from: NodeId,
+    to: NodeId,
+    state: S,
+    mut step: F,
+    breadth_first: bool,
+  ) -> impl Iterator<Item = Vec<NodeId>> + 'a
+  where
+    S: 'a,
+    F: FnMut(&S, NodeId) -> Option<S> + 'a,
+  {
+    let mut queue = VecDeque::from([(vec![from], state)]);
+    from_fn(move || loop {
+      let (path, state) = if breadth_first {
+        queue.pop_front()?
+      } else {
+        queue.pop_back()?
+      };
+      let last = *path.last().unwrap();
+      if last == to {
+        return Some(path);
+      }
+      let extended: Vec<_> = self.adjacency[last]
+        .iter()
+        .filter_map(|next| {
+          let next_state = step(&state, *next)?;
+          let mut extended = path.clone();
+          extended.push(*next);
+          Some((extended, next_state))
+        })
+        .collect();
+      // Depth first takes from the back, so the neighbours go there in reverse to keep their order
+      if breadth_first {
+        queue.extend(extended);
+      } else {
+        queue.extend(extended.into_iter().rev());
+      }
+    })
+  }
+
+  // The number of paths from `from` to `to`, without listing them. Walking to a node turns the
+  // state of the path into the state `step` returns, or is not allowed if it returns None. Counts
+  // are memoised per node and state, so the state should hold only what decides where the path can
+  // still go. `step` must not allow infinitely many paths.
+  pub fn count_paths<S, F>(&self, from: NodeId, to: NodeId, state: S, mut step: F) -> usize
+  where
+    S: Clone + Eq + Hash,
+    F: FnMut(&S, NodeId) -> Option<S>,
+  {
+    let mut memo = HashMap::new();
+    self.count_paths_from(from, to, state, &mut step, &mut memo)
+  }
+
+  fn count_paths_from<S, F>(
+    &self,
+    node: NodeId,
+    to: NodeId,
+    state: S,
+    step: &mut F,
+    memo: &mut HashMap<(NodeId, S), usize>,
+  ) -> usize
+  where
+    S: Clone + Eq + Hash,
+    F: FnMut(&S, NodeId) -> Option<S>,
+  {
+    if node == to {
+      return 1;
+    }
+    if let Some(count) = memo.get(&(node, state.clone())) {
+      return *count;
+    }
+    let mut res = 0;
+    for next in &self.adjacency[node] {
+      if let Some(next_state) = step(&state, *next) {
+        res += self.count_paths_from(*next, to, next_state, step, memo);
+      }
+    }
+    memo.insert((node, state), res);
+    res
+  }
+
+  // The graph in the DOT language of Graphviz. `attributes` gives the DOT attributes of a node,
+  // like "shape=box", or an empty string for none.
+  pub fn to_dot<F: Fn(&A) -> String>(&self, attributes: F) -> String {
+    let mut res = String::from("graph {\n");
+    for id in self.nodes() {
+      let attrs = attributes(&self.attrs[id]);
+      if attrs.is_empty() {
+        writeln!(res, "  \"{}\";", self.labels[id]).unwrap();
+      } else {
+        writeln!(res, "  \"{}\" [{}];", self.labels[id], attrs).unwrap();
+      }
+    }
+    for (a, b) in self.edges() {
+      writeln!(res, "  \"{}\" -- \"{}\";", self.labels[a], self.labels[b]).unwrap();
+    }
+    res.push('}');
+    res
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Nodes whose label starts with a lowercase letter are small and may be visited once
+  fn caves(edges: &str) -> Graph<bool> {
+    let small = |label: &str| label.starts_with(|c: char| c.is_lowercase());
+    let mut graph = Graph::new();
+    for line in edges.lines() {
+      let (a, b) = line.split_once('-').unwrap();
+      let (a, b) = (graph.intern(a, small), graph.intern(b, small));
+      graph.add_edge(a, b);
+    }
+    graph
+  }
+
+  fn step(graph: &Graph<bool>) -> impl Fn(&Vec<NodeId>, NodeId) -> Option<Vec<NodeId>> + '_ {
+    move |visited, next| {
+      if !*graph.attr(next) {
+        Some(visited.clone())
+      } else if visited.contains(&next) {
+        None
+      } else {
+        let mut res = visited.clone();
+        res.push(next);
+        res.sort_unstable();
+        Some(res)
+      }
+    }
+  }
+
+  // The number of paths from start to end, checked against both ways of listing them
+  fn count(graph: &Graph<bool>) -> usize {
+    let (start, end) = (graph.id("start").unwrap(), graph.id("end").unwrap());
+    let res = graph.count_paths(start, end, vec![start], step(graph));
+    let mut paths: Vec<_> = graph.paths(start, end, vec![start], step(graph)).collect();
+    let mut shortest: Vec<_> = graph.shortest_paths(start, end, vec![start], step(graph)).collect();
+    assert!(shortest.windows(2).all(|w| w[0].len() <= w[1].len()));
+    assert_eq!(paths.len(), res);
+    paths.sort();
+    shortest.sort();
+    assert_eq!(paths, shortest);
+    res
+  }
+
+  #[test]
+  fn interns_every_label_once() {
+    let mut graph = Graph::new();
+    let a = graph.intern("a", |_| 1);
+    let b = graph.intern("b", |_| 2);
+    assert_eq!(graph.intern("a", |_| 3), a);
+    assert_eq!(graph.len(), 2);
+    assert_eq!((graph.id("a"), graph.id("b"), graph.id("c")), (Some(a), Some(b), None));
+    assert_eq!((graph.label(b), *graph.attr(a)), ("b", 1));
+    graph.add_edge(a, b);
+    graph.add_edge(b, a);
+    assert_eq!(graph.edges().collect::<Vec<_>>(), vec![(a, b)]);
+  }
+
+  #[test]
+  fn counts_the_paths_of_the_examples() {
+    assert_eq!(count(&caves("start-A\nstart-b\nA-c\nA-b\nb-d\nA-end\nb-end")), 10);
+    let example = "dc-end\nHN-start\nstart-kj\ndc-start\ndc-HN\nLN-dc\nHN-end\nkj-sa\nkj-HN\nkj-dc";
+    assert_eq!(count(&caves(example)), 19);
+    let example = "fs-end\nhe-DX\nfs-he\nstart-DX\npj-DX\nend-zg\nzg-sl\nzg-pj\npj-he\n\
+                   RW-he\nfs-DX\npj-RW\nzg-RW\nstart-pj\nhe-WI\nzg-he\npj-fs\nstart-RW";
+    assert_eq!(count(&caves(example)), 226);
+  }
+
+  #[test]
+  fn counts_the_paths_of_more_than_11_caves() {
+    // A ladder of two rows of 7 small caves
+    let mut edges = vec![String::from("start-t0"), String::from("b6-end")];
+    for i in 0..7 {
+      edges.push(format!("t{}-b{}", i, i));
+      if i < 6 {
+        edges.push(format!("t{}-t{}", i, i + 1));
+        edges.push(format!("b{}-b{}", i, i + 1));
+      }
+    }
+    let graph = caves(&edges.join("\n"));
+    assert_eq!(graph.len(), 16);
+    assert_eq!(count(&graph), 64);
+  }
+
+  #[test]
+  fn writes_dot() {
+    let graph = caves("start-END");
+    let dot = graph.to_dot(|small| if *small { String::new() } else { String::from("shape=box") });
+    assert_eq!(dot, "graph {\n  \"start\";\n  \"END\" [shape=box];\n  \"start\" -- \"END\";\n}");
+  }
+}
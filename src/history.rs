@@ -0,0 +1,237 @@
+use crate::days::Timings;
+use crate::parser::MyErr;
+use chrono::prelude::*;
+use log::warn;
+use nom::bytes::complete::is_not;
+use nom::character::complete::{char, u8};
+use nom::combinator::{all_consuming, opt};
+use nom::number::complete::double;
+use nom::sequence::{preceded, tuple};
+use nom::IResult;
+use std::fs::{read_to_string, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::process::Command;
+
+// Tab-separated: timestamp, commit, machine, day, part, seconds and source, which lines from
+// before sources were recorded leave out
+const HISTORY_FILE: &str = ".history";
+const SPARK_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const SPARK_LENGTH: usize = 20;
+// The source of the timings of `run`, on the puzzle inputs
+pub const RUN_SOURCE: &str = "run";
+
+#[derive(Debug, Clone)]
+struct Entry {
+  timestamp: String,
+  commit: String,
+  machine: String,
+  day: u8,
+  part: u8,
+  seconds: f64,
+  source: String,
+}
+
+fn parse_field(input: &str) -> IResult<&str, String> {
+  let (cont, field) = is_not("\t\n")(input)?;
+  Ok((cont, field.to_owned()))
+}
+
+fn parse_entry(input: &str) -> IResult<&str, Entry> {
+  let (cont, (timestamp, _, commit, _, machine, _, day, _, part, _, seconds, source)) = tuple((
+    parse_field,
+    char('\t'),
+    parse_field,
+    char('\t'),
+    parse_field,
+    char('\t'),
+    u8,
+    char('\t'),
+    u8,
+    char('\t'),
+    double,
+    opt(preceded(char('\t'), parse_field)),
+  ))(input)?;
+  Ok((
+    cont,
+    Entry {
+      timestamp,
+      commit,
+      machine,
+      day,
+      part,
+      seconds,
+      source: source.unwrap_or_else(|| String::from(RUN_SOURCE)),
+    },
+  ))
+}
+
+// Runs a command and returns its trimmed output, or "unknown" if it could not be run
+fn command_output(program: &str, args: &[&str]) -> String {
+  Command::new(program)
+    .args(args)
+    .output()
+    .ok()
+    .filter(|o| o.status.success())
+    .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_owned())
+    .filter(|s| !s.is_empty())
+    .unwrap_or_else(|| String::from("unknown"))
+}
+
+fn current_commit() -> String {
+  command_output("git", &["rev-parse", "--short", "HEAD"])
+}
+
+fn current_machine() -> String {
+  command_output("hostname", &[])
+}
+
+// Appends the timings of each day, which were measured by `source`
+pub fn record(source: &str, results: &[(usize, Timings)]) -> Result<(), MyErr> {
+  if results.is_empty() {
+    return Ok(());
+  }
+  let timestamp = Utc::now().format("%Y-%m-%dT%H:%M:%SZ");
+  let commit = current_commit();
+  let machine = current_machine();
+
+  let mut file = OpenOptions::new()
+    .create(true)
+    .append(true)
+    .open(HISTORY_FILE)?;
+  for (day, timings) in results {
+    for (part, duration) in [(1, timings.part_1), (2, timings.part_2)] {
+      writeln!(
+        file,
+        "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+        timestamp,
+        commit,
+        machine,
+        day,
+        part,
+        duration.as_secs_f64(),
+        source
+      )?;
+    }
+  }
+  Ok(())
+}
+
+fn load() -> Result<Vec<Entry>, MyErr> {
+  let input_string = match read_to_string(HISTORY_FILE) {
+    Ok(s) => s,
+    Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+    Err(e) => return Err(e.into()),
+  };
+  // A corrupt line, for example from a run that was killed while writing, only loses that entry
+  let mut entries = vec![];
+  for (i, line) in input_string.lines().enumerate() {
+    match all_consuming(parse_entry)(line) {
+      Ok((_, entry)) => entries.push(entry),
+      Err(_) if line.trim().is_empty() => {}
+      Err(_) => warn!("Skipping line {} of {}, which is not a timing", i + 1, HISTORY_FILE),
+    }
+  }
+  Ok(entries)
+}
+
+fn sparkline(values: &[f64]) -> String {
+  let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+  let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+  values
+    .iter()
+    .map(|v| {
+      if max > min {
+        let i = ((v - min) / (max - min) * (SPARK_CHARS.len() - 1) as f64).round() as usize;
+        SPARK_CHARS[i]
+      } else {
+        SPARK_CHARS[0]
+      }
+    })
+    .collect()
+}
+
+// Prints the recorded timings of this machine, and flags every part whose latest run is more than
+// `threshold` percent slower than its best run. Returns the number of regressions found.
+pub fn show(day: Option<usize>, threshold: f64) -> Result<usize, MyErr> {
+  let machine = current_machine();
+  let entries: Vec<Entry> = load()?
+    .into_iter()
+    .filter(|e| e.machine == machine)
+    .filter(|e| day.is_none_or(|d| e.day as usize == d))
+    .collect();
+
+  if entries.is_empty() {
+    println!("No timings recorded on {} yet", machine);
+    return Ok(0);
+  }
+
+  // The timings of `run` first, then those of each benchmark in the order they first appear
+  let mut sources = vec![RUN_SOURCE];
+  for e in &entries {
+    if !sources.contains(&e.source.as_str()) {
+      sources.push(&e.source);
+    }
+  }
+
+  let mut regressions = 0;
+  for (source, d, p) in sources
+    .iter()
+    .flat_map(|source| (1..=25).flat_map(move |d| (1..=2).map(move |p| (source, d, p))))
+  {
+    let runs: Vec<&Entry> = entries
+      .iter()
+      .filter(|e| e.source == *source && e.day == d && e.part == p)
+      .collect();
+    let (latest, best) = match (
+      runs.last(),
+      runs.iter().min_by(|a, b| a.seconds.total_cmp(&b.seconds)),
+    ) {
+      (Some(latest), Some(best)) => (latest, best),
+      _ => continue,
+    };
+    let recent: Vec<f64> = runs
+      .iter()
+      .skip(runs.len().saturating_sub(SPARK_LENGTH))
+      .map(|e| e.seconds)
+      .collect();
+    let change = (latest.seconds / best.seconds - 1.0) * 100.0;
+    let is_regression = change > threshold;
+    let prefix = if *source == RUN_SOURCE { String::new() } else { format!("{}: ", source) };
+    if is_regression {
+      regressions += 1;
+    }
+    println!(
+      "{}Day {:>2} part {}  {:<20}  latest {:.6}s ({} @ {})  best {:.6}s ({} @ {})  {:+.1}%{}",
+      prefix,
+      d,
+      p,
+      sparkline(&recent),
+      latest.seconds,
+      latest.commit,
+      latest.timestamp,
+      best.seconds,
+      best.commit,
+      best.timestamp,
+      change,
+      if is_regression { "  REGRESSION" } else { "" }
+    );
+  }
+  Ok(regressions)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_entries_with_and_without_a_source() {
+    let (_, entry) = all_consuming(parse_entry)("2021-12-01T00:00:00Z\tabc1234\tpc\t1\t2\t0.5")
+      .unwrap();
+    assert_eq!((entry.day, entry.part, entry.seconds), (1, 2, 0.5));
+    assert_eq!(entry.source, RUN_SOURCE);
+    let line = "2021-12-01T00:00:00Z\tabc1234\tpc\t18\t1\t0.25\tbench flat scale 1";
+    let (_, entry) = all_consuming(parse_entry)(line).unwrap();
+    assert_eq!((entry.day, entry.part, entry.seconds), (18, 1, 0.25));
+    assert_eq!(entry.source, "bench flat scale 1");
+  }
+}
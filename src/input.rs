@@ -0,0 +1,326 @@
+use crate::parser::MyErr;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use log::info;
+use std::env;
+use std::fmt::{self, Display, Formatter};
+use std::fs::{self, read_to_string};
+use std::io::ErrorKind;
+use std::path::Path;
+
+pub const KEY_FILE: &str = ".inputkey";
+pub const KEY_VAR: &str = "AOC_INPUT_KEY";
+const KEY_LENGTH: usize = 32;
+// Encrypted files start with the random nonce they were encrypted with
+const NONCE_LENGTH: usize = 12;
+
+// Something `normalise` had to change to bring an input in canonical form
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+  ByteOrderMark,
+  // The number of lines that ended in CRLF
+  CarriageReturns(usize),
+  // The number of lines that ended in spaces or tabs
+  TrailingWhitespace(usize),
+  // The number of empty lines after the last line
+  TrailingBlankLines(usize),
+}
+
+impl Display for Change {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match self {
+      Change::ByteOrderMark => write!(f, "removed a byte order mark"),
+      Change::CarriageReturns(n) => write!(f, "converted {} CRLF line endings to LF", n),
+      Change::TrailingWhitespace(n) => write!(f, "removed trailing whitespace from {} lines", n),
+      Change::TrailingBlankLines(n) => write!(f, "removed {} blank lines at the end", n),
+    }
+  }
+}
+
+// Brings an input in the canonical form the parsers expect: LF line endings, no whitespace at the
+// end of lines and no newline after the last line. The single final newline every input comes with
+// is removed without reporting it as a change.
+pub fn normalise(input: &str) -> (String, Vec<Change>) {
+  let mut changes = vec![];
+  let input = match input.strip_prefix('\u{feff}') {
+    Some(rest) => {
+      changes.push(Change::ByteOrderMark);
+      rest
+    }
+    None => input,
+  };
+
+  let mut carriage_returns = 0;
+  let mut trailing_whitespace = 0;
+  let mut lines: Vec<&str> = vec![];
+  for line in input.split('\n') {
+    let without_cr = match line.strip_suffix('\r') {
+      Some(rest) => {
+        carriage_returns += 1;
+        rest
+      }
+      None => line,
+    };
+    let trimmed = without_cr.trim_end();
+    if trimmed.len() < without_cr.len() {
+      trailing_whitespace += 1;
+    }
+    lines.push(trimmed);
+  }
+
+  let mut blank_lines = 0;
+  while lines.len() > 1 && lines.last() == Some(&"") {
+    lines.pop();
+    blank_lines += 1;
+  }
+
+  if carriage_returns > 0 {
+    changes.push(Change::CarriageReturns(carriage_returns));
+  }
+  if trailing_whitespace > 0 {
+    changes.push(Change::TrailingWhitespace(trailing_whitespace));
+  }
+  if blank_lines > 1 {
+    changes.push(Change::TrailingBlankLines(blank_lines - 1));
+  }
+  (lines.join("\n"), changes)
+}
+
+// Where the encrypted version of an input file is stored
+pub fn encrypted_path(fp: &str) -> String {
+  format!("{}.enc", fp)
+}
+
+fn crypto_err(message: &str) -> MyErr {
+  MyErr::CryptoError(message.to_owned())
+}
+
+// The key in the environment variable, or else in the key file. None if neither is set.
+pub fn load_key() -> Result<Option<Key>, MyErr> {
+  let hex = match env::var(KEY_VAR) {
+    Ok(hex) => hex,
+    Err(_) => match read_to_string(KEY_FILE) {
+      Ok(hex) => hex,
+      Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+      Err(e) => return Err(e.into()),
+    },
+  };
+  let hex = hex.trim();
+  if hex.len() != KEY_LENGTH * 2 || !hex.is_ascii() {
+    return Err(crypto_err("the key should be 64 hexadecimal characters"));
+  }
+  let bytes: Result<Vec<u8>, _> = (0..KEY_LENGTH)
+    .map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16))
+    .collect();
+  match bytes {
+    Ok(bytes) => Ok(Some(*Key::from_slice(&bytes))),
+    Err(_) => Err(crypto_err("the key should be 64 hexadecimal characters")),
+  }
+}
+
+fn require_key() -> Result<Key, MyErr> {
+  load_key()?.ok_or_else(|| {
+    MyErr::CryptoError(format!(
+      "no key is set, put one in {} or the {} environment variable",
+      KEY_FILE, KEY_VAR
+    ))
+  })
+}
+
+// A new random key, in the hexadecimal form `load_key` reads
+pub fn generate_key() -> String {
+  rand::random::<[u8; KEY_LENGTH]>()
+    .iter()
+    .map(|b| format!("{:02x}", b))
+    .collect()
+}
+
+pub fn encrypt(key: &Key, input: &str) -> Vec<u8> {
+  let nonce: [u8; NONCE_LENGTH] = rand::random();
+  let ciphertext = ChaCha20Poly1305::new(key)
+    .encrypt(Nonce::from_slice(&nonce), input.as_bytes())
+    .expect("Inputs are far too short to exceed the maximum message length");
+  let mut res = nonce.to_vec();
+  res.extend(ciphertext);
+  res
+}
+
+pub fn decrypt(key: &Key, data: &[u8]) -> Result<String, MyErr> {
+  if data.len() < NONCE_LENGTH {
+    return Err(crypto_err("the encrypted input is too short"));
+  }
+  let (nonce, ciphertext) = data.split_at(NONCE_LENGTH);
+  let plaintext = ChaCha20Poly1305::new(key)
+    .decrypt(Nonce::from_slice(nonce), ciphertext)
+    .map_err(|_| crypto_err("could not decrypt the input, is the key right?"))?;
+  String::from_utf8(plaintext).map_err(|_| crypto_err("the decrypted input is not UTF-8"))
+}
+
+// Reads an input file, or else decrypts its encrypted version
+fn read_raw(fp: &str) -> Result<String, MyErr> {
+  match read_to_string(fp) {
+    Err(e) if e.kind() == ErrorKind::NotFound && Path::new(&encrypted_path(fp)).exists() => {
+      decrypt(&require_key()?, &fs::read(encrypted_path(fp))?)
+    }
+    res => Ok(res?),
+  }
+}
+
+// Writes an input, encrypted if a key is set, and returns the path it was written to. An
+// unlocked plain text version is removed, since `read` would prefer it over the new input.
+pub fn write(fp: &str, input: &str) -> Result<String, MyErr> {
+  match load_key()? {
+    Some(key) => {
+      let path = encrypted_path(fp);
+      fs::write(&path, encrypt(&key, input))?;
+      if Path::new(fp).exists() {
+        fs::remove_file(fp)?;
+      }
+      Ok(path)
+    }
+    None => {
+      fs::write(fp, input)?;
+      Ok(fp.to_owned())
+    }
+  }
+}
+
+// Whether an input file exists, either as plain text or encrypted
+pub fn exists(fp: &str) -> bool {
+  Path::new(fp).exists() || Path::new(&encrypted_path(fp)).exists()
+}
+
+// Encrypts every given input file that exists and removes the plain text. Encrypted files that
+// already hold the same input are kept as they are, so locking again does not change them.
+// Returns the paths of the files that were locked.
+pub fn lock(fps: &[String]) -> Result<Vec<String>, MyErr> {
+  let key = require_key()?;
+  let mut res = vec![];
+  for fp in fps.iter().filter(|fp| Path::new(fp).exists()) {
+    let input = read_to_string(fp)?;
+    let enc = encrypted_path(fp);
+    let unchanged = fs::read(&enc)
+      .ok()
+      .and_then(|data| decrypt(&key, &data).ok())
+      .is_some_and(|old| old == input);
+    if !unchanged {
+      fs::write(&enc, encrypt(&key, &input))?;
+    }
+    fs::remove_file(fp)?;
+    res.push(fp.clone());
+  }
+  Ok(res)
+}
+
+// Decrypts every given input file that only exists encrypted. The encrypted files are kept, since
+// they are the ones to commit. Returns the paths of the files that were unlocked.
+pub fn unlock(fps: &[String]) -> Result<Vec<String>, MyErr> {
+  let key = require_key()?;
+  let mut res = vec![];
+  for fp in fps {
+    let enc = encrypted_path(fp);
+    if Path::new(fp).exists() || !Path::new(&enc).exists() {
+      continue;
+    }
+    fs::write(fp, decrypt(&key, &fs::read(&enc)?)?)?;
+    res.push(fp.clone());
+  }
+  Ok(res)
+}
+
+// Reads an input file in canonical form, logging what had to change
+pub fn read(fp: &str) -> Result<String, MyErr> {
+  let (input, changes) = normalise(&read_raw(fp)?);
+  for change in changes {
+    info!("{}: {}", fp, change);
+  }
+  Ok(input)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn keeps_canonical_inputs() {
+    assert_eq!(normalise("1\n2\n"), (String::from("1\n2"), vec![]));
+    assert_eq!(normalise("1\n\n2"), (String::from("1\n\n2"), vec![]));
+  }
+
+  #[test]
+  fn normalises_pasted_inputs() {
+    assert_eq!(
+      normalise("\u{feff}1\n"),
+      (String::from("1"), vec![Change::ByteOrderMark])
+    );
+    assert_eq!(
+      normalise("1\r\n2\r\n\r\n3\r\n"),
+      (String::from("1\n2\n\n3"), vec![Change::CarriageReturns(4)])
+    );
+    assert_eq!(
+      normalise("1 \n2\t\n3"),
+      (String::from("1\n2\n3"), vec![Change::TrailingWhitespace(2)])
+    );
+    assert_eq!(
+      normalise("1\n\n\n"),
+      (String::from("1"), vec![Change::TrailingBlankLines(2)])
+    );
+    assert_eq!(
+      normalise("\u{feff}1  \r\n2\r\n\r\n"),
+      (
+        String::from("1\n2"),
+        vec![
+          Change::ByteOrderMark,
+          Change::CarriageReturns(3),
+          Change::TrailingWhitespace(1),
+          Change::TrailingBlankLines(1),
+        ]
+      )
+    );
+  }
+
+  #[test]
+  fn decrypts_what_it_encrypts() {
+    let key = *Key::from_slice(&[7; KEY_LENGTH]);
+    let encrypted = encrypt(&key, "1\n2\n3");
+    assert_eq!(decrypt(&key, &encrypted).unwrap(), "1\n2\n3");
+    // Every encryption gets a new nonce
+    assert_ne!(encrypt(&key, "1\n2\n3"), encrypted);
+  }
+
+  #[test]
+  fn refuses_wrong_keys_and_tampered_inputs() {
+    let key = *Key::from_slice(&[7; KEY_LENGTH]);
+    let encrypted = encrypt(&key, "1\n2\n3");
+    let wrong_key = Key::from_slice(&[8; KEY_LENGTH]);
+    assert!(matches!(decrypt(wrong_key, &encrypted), Err(MyErr::CryptoError(_))));
+    let mut tampered = encrypted.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    assert!(matches!(decrypt(&key, &tampered), Err(MyErr::CryptoError(_))));
+    assert!(matches!(decrypt(&key, &encrypted[..4]), Err(MyErr::CryptoError(_))));
+  }
+
+  #[test]
+  fn locks_and_unlocks_inputs() {
+    let dir = env::temp_dir().join(format!("aoc-input-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let fp = dir.join("day01.txt").to_str().unwrap().to_owned();
+    let fps = vec![fp.clone()];
+    fs::write(&fp, "1\n2\n3").unwrap();
+    // No other test reads the key
+    env::set_var(KEY_VAR, "07".repeat(KEY_LENGTH));
+
+    assert_eq!(lock(&fps).unwrap(), fps);
+    assert!(!Path::new(&fp).exists());
+    let encrypted = fs::read(encrypted_path(&fp)).unwrap();
+    assert_eq!(read(&fp).unwrap(), "1\n2\n3");
+    assert_eq!(unlock(&fps).unwrap(), fps);
+    assert_eq!(fs::read_to_string(&fp).unwrap(), "1\n2\n3");
+    // Locking the same input again keeps the encrypted file as it was
+    lock(&fps).unwrap();
+    assert_eq!(fs::read(encrypted_path(&fp)).unwrap(), encrypted);
+
+    env::remove_var(KEY_VAR);
+    fs::remove_dir_all(&dir).unwrap();
+  }
+}
@@ -0,0 +1,6 @@
+pub mod bigint;
+pub mod days;
+pub mod geometry;
+pub mod graph;
+pub mod input;
+pub mod parser;
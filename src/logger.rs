@@ -0,0 +1,35 @@
+use log::{LevelFilter, Log, Metadata, Record};
+
+// Writes log events of this crate to stderr, so they never mix with the answers on stdout
+struct StderrLogger;
+
+impl Log for StderrLogger {
+  fn enabled(&self, metadata: &Metadata) -> bool {
+    metadata.level() <= log::max_level() && metadata.target().starts_with(env!("CARGO_CRATE_NAME"))
+  }
+
+  fn log(&self, record: &Record) {
+    if self.enabled(record.metadata()) {
+      eprintln!("[{:<5} {}] {}", record.level(), record.target(), record.args());
+    }
+  }
+
+  fn flush(&self) {}
+}
+
+static LOGGER: StderrLogger = StderrLogger;
+
+// -q only shows errors, no flags show info, -v adds debug events and -vv adds trace events
+pub fn init(verbosity: u64, quiet: bool) {
+  let level = if quiet {
+    LevelFilter::Error
+  } else {
+    match verbosity {
+      0 => LevelFilter::Info,
+      1 => LevelFilter::Debug,
+      _ => LevelFilter::Trace,
+    }
+  };
+  log::set_logger(&LOGGER).expect("A logger was already initialised");
+  log::set_max_level(level);
+}
@@ -1,12 +1,23 @@
-#[allow(dead_code)]
-mod parser;
+use aoc_template::{days, input, parser, with_day};
 use chrono::prelude::*;
 use clap::{App, AppSettings, Arg, SubCommand};
 use days::*;
-use std::fs;
-use std::time::Instant;
+use log::{info, warn};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::path::Path;
+use std::time::Duration;
 
-mod days;
+mod bench;
+mod download;
+mod explore;
+mod history;
+mod logger;
+mod report;
+mod roundtrip;
+mod serve;
+mod status;
+mod viz;
 
 const YEAR: usize = 2021;
 
@@ -16,6 +27,22 @@ fn main() {
     .author("Rik van Toor <rik@rikvt.dev>")
     .about("A template for solving Advent of Code puzzles in Rust")
     .setting(AppSettings::SubcommandRequiredElseHelp)
+    .arg(
+      Arg::with_name("verbose")
+        .short("v")
+        .long("verbose")
+        .multiple(true)
+        .global(true)
+        .help("Show debug events of the solvers, or trace events when given twice"),
+    )
+    .arg(
+      Arg::with_name("quiet")
+        .short("q")
+        .long("quiet")
+        .global(true)
+        .conflicts_with("verbose")
+        .help("Only show errors besides the output"),
+    )
     .subcommands(vec![
       SubCommand::with_name("run")
         .about("Execute one or multiple days. Runs today's puzzle by default.")
@@ -30,6 +57,178 @@ fn main() {
             .long("all")
             .help("Runs all days sequentially"),
         ),
+      SubCommand::with_name("history")
+        .about("Show recorded run times on this machine and flag regressions.")
+        .arg(
+          Arg::with_name("day")
+            .help("The number of the day you want to see the history of")
+            .takes_value(true),
+        )
+        .arg(
+          Arg::with_name("threshold")
+            .short("t")
+            .long("threshold")
+            .help("How many percent slower than the best run counts as a regression")
+            .takes_value(true)
+            .default_value("20"),
+        ),
+      SubCommand::with_name("report")
+        .about("Run all days and write a table of their run times to README.md."),
+      SubCommand::with_name("viz")
+        .about("Show the intermediate states of a day as an animation, or export them as images.")
+        .arg(
+          Arg::with_name("day")
+            .help("The number of the day you want to visualise")
+            .required(true)
+            .takes_value(true),
+        )
+        .arg(
+          Arg::with_name("out")
+            .short("o")
+            .long("out")
+            .help("Write the frames as numbered PPM/PBM images to this directory instead")
+            .takes_value(true),
+        )
+        .arg(
+          Arg::with_name("delay")
+            .short("d")
+            .long("delay")
+            .help("The number of milliseconds between frames in the terminal")
+            .takes_value(true)
+            .default_value("100"),
+        )
+        .arg(
+          Arg::with_name("scale")
+            .short("s")
+            .long("scale")
+            .help("The number of pixels per cell in exported images")
+            .takes_value(true)
+            .default_value("1"),
+        ),
+      SubCommand::with_name("gen")
+        .about("Generate a random input for a day and print it.")
+        .arg(
+          Arg::with_name("day")
+            .help("The number of the day you want to generate an input for")
+            .required(true)
+            .takes_value(true),
+        )
+        .arg(
+          Arg::with_name("seed")
+            .long("seed")
+            .help("The seed of the random generator. A random seed is used by default.")
+            .takes_value(true),
+        )
+        .arg(
+          Arg::with_name("scale")
+            .long("scale")
+            .help("How many times larger than a real input the generated input should be")
+            .takes_value(true)
+            .default_value("1"),
+        ),
+      SubCommand::with_name("roundtrip")
+        .about("Check that generated inputs parse, and that printing and parsing them again is lossless.")
+        .arg(
+          Arg::with_name("day")
+            .help("The number of the day you want to check. Checks all days by default.")
+            .takes_value(true),
+        )
+        .arg(
+          Arg::with_name("cases")
+            .short("n")
+            .long("cases")
+            .help("The number of inputs to generate per day")
+            .takes_value(true)
+            .default_value("100"),
+        )
+        .arg(
+          Arg::with_name("seed")
+            .long("seed")
+            .help("The seed of the random generator. A random seed is used by default.")
+            .takes_value(true),
+        ),
+      SubCommand::with_name("status")
+        .about("Print a calendar of all days, showing which are stubs, have an input and give the accepted answers.")
+        .arg(
+          Arg::with_name("stars")
+            .short("s")
+            .long("stars")
+            .help("Also show the stars collected on the site"),
+        ),
+      SubCommand::with_name("accept")
+        .about("Record the current answers of a day as accepted, after the site accepted them.")
+        .arg(
+          Arg::with_name("day")
+            .help("The number of the day whose answers were accepted")
+            .required(true)
+            .takes_value(true),
+        )
+        .arg(
+          Arg::with_name("part")
+            .short("p")
+            .long("part")
+            .help("Only accept the answer of this part")
+            .takes_value(true)
+            .possible_values(&["1", "2"]),
+        ),
+      SubCommand::with_name("explore")
+        .about("Parse the input of a day once, and explore it with commands to run parts, print the input and change parameters.")
+        .arg(
+          Arg::with_name("day")
+            .help("The number of the day you want to explore")
+            .required(true)
+            .takes_value(true),
+        ),
+      SubCommand::with_name("disasm")
+        .about("Disassemble a day 16 transmission, listing its packets with their bit offsets and the expression it evaluates.")
+        .arg(
+          Arg::with_name("transmission")
+            .help("The transmission in hexadecimal. The input of day 16 is used by default.")
+            .takes_value(true),
+        ),
+      SubCommand::with_name("dot")
+        .about("Print the caves of day 12 in the DOT language of Graphviz, with big caves as boxes."),
+      SubCommand::with_name("bench")
+        .about("Compare the tree and the flat representation of day 18 snailfish numbers on a generated input.")
+        .arg(
+          Arg::with_name("scale")
+            .short("s")
+            .long("scale")
+            .help("The input has 100 numbers per unit of scale")
+            .takes_value(true)
+            .default_value("1"),
+        )
+        .arg(
+          Arg::with_name("runs")
+            .short("n")
+            .long("runs")
+            .help("Every step is timed this many times, and the fastest counts")
+            .takes_value(true)
+            .default_value("5"),
+        ),
+      SubCommand::with_name("serve")
+        .about("Serve the solutions over HTTP on localhost. POST an input to /<year>/<day> to solve it.")
+        .arg(
+          Arg::with_name("port")
+            .short("p")
+            .long("port")
+            .help("The port to listen on")
+            .takes_value(true)
+            .default_value("8080"),
+        ),
+      SubCommand::with_name("inputs")
+        .about("Manage encrypted inputs, which can be committed unlike the plain text ones.")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommands(vec![
+          SubCommand::with_name("lock")
+            .about("Encrypt all plain text inputs to inputs/dayNN.txt.enc and remove the plain text."),
+          SubCommand::with_name("unlock")
+            .about("Decrypt all encrypted inputs to plain text, keeping the encrypted files."),
+          SubCommand::with_name("keygen").about(&*format!(
+            "Write a new random key to {}, if there is no key yet.",
+            input::KEY_FILE
+          )),
+        ]),
       SubCommand::with_name("get-input")
         .about("Download an input file. By default it will download today's input.")
         .arg(
@@ -41,38 +240,186 @@ fn main() {
           Arg::with_name("all")
             .short("a")
             .long("all")
-            .help("Downloads input for all days sequentially"),
+            .help("Downloads input for all days that have unlocked sequentially"),
+        )
+        .arg(
+          Arg::with_name("force")
+            .short("f")
+            .long("force")
+            .help("Download inputs again even if they already exist"),
         ),
     ])
     .get_matches();
 
+  logger::init(matches.occurrences_of("verbose"), matches.is_present("quiet"));
+
   if let Some(matches) = matches.subcommand_matches("run") {
-    if matches.is_present("all") {
-      run_all_days();
+    let timings = if matches.is_present("all") {
+      run_all_days()
     } else {
       match matches.value_of("day") {
-        Some(day) => run_day(parse_day(day)),
+        Some(day) => run_days(&[parse_day(day)]),
         None => {
-          println!("No day parameter specified, attempting to run today");
+          info!("No day parameter specified, attempting to run today");
           let now_day = get_today();
-          println!("Running day {}", now_day);
-          run_day(now_day);
+          info!("Running day {}", now_day);
+          run_days(&[now_day])
+        }
+      }
+    };
+    if let Err(e) = history::record(history::RUN_SOURCE, &timings) {
+      warn!("Could not record timings: {:?}", e);
+    }
+  } else if let Some(matches) = matches.subcommand_matches("history") {
+    let day = matches.value_of("day").map(parse_day);
+    let threshold = matches.value_of("threshold").unwrap();
+    match threshold.parse::<f64>().map(|t| (t, history::show(day, t))) {
+      Err(_) => println!("The threshold should be a number of percent, not {}", threshold),
+      Ok((_, Ok(0))) => {}
+      Ok((t, Ok(n))) => println!("Found {} regression(s) of more than {}%", n, t),
+      Ok((_, Err(e))) => println!("Could not read timing history: {:?}", e),
+    }
+  } else if matches.subcommand_matches("report").is_some() {
+    if let Err(e) = report::report() {
+      println!("Could not update the README: {:?}", e);
+    }
+  } else if let Some(matches) = matches.subcommand_matches("viz") {
+    let day = parse_day(matches.value_of("day").unwrap());
+    let output = match matches.value_of("out") {
+      Some(dir) => viz::Output::Images(
+        Path::new(dir),
+        parse_number(matches.value_of("scale").unwrap(), "scale"),
+      ),
+      None => viz::Output::Terminal(Duration::from_millis(parse_number(
+        matches.value_of("delay").unwrap(),
+        "delay",
+      ))),
+    };
+    if let Err(e) = visualize_day(day, output) {
+      println!("{:?}", e);
+    }
+  } else if let Some(matches) = matches.subcommand_matches("gen") {
+    let day = parse_day(matches.value_of("day").unwrap());
+    let scale = parse_number(matches.value_of("scale").unwrap(), "scale");
+    let seed = match matches.value_of("seed") {
+      Some(seed) => parse_number(seed, "seed"),
+      None => rand::random(),
+    };
+    info!("Generating input for day {} with seed {}", day, seed);
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    print!("{}", with_day!(day, D => D::generate(&mut rng, scale)));
+  } else if let Some(matches) = matches.subcommand_matches("roundtrip") {
+    let days = match matches.value_of("day") {
+      Some(day) => vec![parse_day(day)],
+      None => (1..=25).collect(),
+    };
+    let cases = parse_number(matches.value_of("cases").unwrap(), "number of cases");
+    let seed = match matches.value_of("seed") {
+      Some(seed) => parse_number(seed, "seed"),
+      None => rand::random(),
+    };
+    info!("Checking {} inputs per day with seed {}", cases, seed);
+    let mut failures = 0;
+    for day in days {
+      let mut rng = ChaCha8Rng::seed_from_u64(seed);
+      match with_day!(day, D => roundtrip::check::<D, _>(&mut rng, cases)) {
+        Ok(()) => println!("Day {:>2}: ok", day),
+        Err(e) => {
+          println!("Day {:>2}: failed on {}", day, e);
+          failures += 1;
+        }
+      }
+    }
+    if failures > 0 {
+      std::process::exit(1);
+    }
+  } else if let Some(matches) = matches.subcommand_matches("status") {
+    if let Err(e) = status::status(matches.is_present("stars")) {
+      println!("{:?}", e);
+    }
+  } else if let Some(matches) = matches.subcommand_matches("accept") {
+    let day = parse_day(matches.value_of("day").unwrap());
+    let part = matches.value_of("part").map(|p| parse_number(p, "part"));
+    if let Err(e) = status::accept(day, part) {
+      println!("{:?}", e);
+    }
+  } else if let Some(matches) = matches.subcommand_matches("explore") {
+    let day = parse_day(matches.value_of("day").unwrap());
+    let input_fp = &format!("inputs/day{:02}.txt", day);
+    if let Err(e) = with_day!(day, D => explore::explore::<D>(input_fp, day)) {
+      println!("{:?}", e);
+    }
+  } else if let Some(matches) = matches.subcommand_matches("disasm") {
+    let transmission = match matches.value_of("transmission") {
+      Some(t) => Ok(t.to_owned()),
+      None => input::read("inputs/day16.txt"),
+    };
+    match transmission.map(|t| day16::disasm(&t)) {
+      Ok(Ok(listing)) => println!("{}", listing),
+      Ok(Err(e)) => println!("Could not disassemble the transmission: {}", e),
+      Err(e) => println!("{:?}", e),
+    }
+  } else if matches.subcommand_matches("dot").is_some() {
+    match day12::Day12::parse_file("inputs/day12.txt") {
+      Ok(edges) => println!("{}", day12::to_dot(&edges)),
+      Err(e) => println!("{:?}", e),
+    }
+  } else if let Some(matches) = matches.subcommand_matches("bench") {
+    let scale = parse_number(matches.value_of("scale").unwrap(), "scale");
+    let runs = parse_number(matches.value_of("runs").unwrap(), "number of runs");
+    match bench::day18(scale, runs) {
+      Ok((table, timings)) => {
+        println!("{}", table);
+        for (source, timings) in timings {
+          if let Err(e) = history::record(&source, &[(18, timings)]) {
+            warn!("Could not record timings: {:?}", e);
+          }
         }
       }
+      Err(e) => println!("{:?}", e),
+    }
+  } else if let Some(matches) = matches.subcommand_matches("serve") {
+    let port = parse_number(matches.value_of("port").unwrap(), "port");
+    if let Err(e) = serve::serve(port) {
+      println!("Could not start the server: {:?}", e);
+    }
+  } else if let Some(matches) = matches.subcommand_matches("inputs") {
+    let input_fps: Vec<String> = (1..=25).map(download::input_path).collect();
+    let res = match matches.subcommand_name() {
+      Some("lock") => input::lock(&input_fps).map(|fps| {
+        for fp in &fps {
+          println!("Locked {}", fp);
+        }
+        println!("Locked {} input(s)", fps.len());
+      }),
+      Some("unlock") => input::unlock(&input_fps).map(|fps| {
+        for fp in &fps {
+          println!("Unlocked {}", fp);
+        }
+        println!("Unlocked {} input(s)", fps.len());
+      }),
+      _ => generate_key(),
+    };
+    if let Err(e) = res {
+      println!("{:?}", e);
     }
   } else if let Some(matches) = matches.subcommand_matches("get-input") {
-    if matches.is_present("all") {
-      download_all_input();
+    let force = matches.is_present("force");
+    let res = if matches.is_present("all") {
+      download::download_all(force)
     } else {
       match matches.value_of("day") {
-        Some(day) => download_input(parse_day(day)),
+        Some(day) => download::download(parse_day(day), force),
         None => {
-          println!("No day parameter specified, attempting to download today's input");
+          info!("No day parameter specified, attempting to download today's input");
           let now_day = get_today();
-          println!("Getting input for day {}", now_day);
-          download_input(now_day);
+          info!("Getting input for day {}", now_day);
+          download::download(now_day, force)
         }
       }
+    };
+    if let Err(e) = res {
+      println!("Could not download inputs: {}", e);
     }
   }
 }
@@ -80,7 +427,7 @@ fn main() {
 fn get_today() -> usize {
   let now = Local::now();
   let now_day = now.day();
-  if now.month() == 12 && now_day >= 1 && now_day <= 25 {
+  if now.month() == 12 && (1..=25).contains(&now_day) {
     now_day.try_into().unwrap()
   } else {
     panic!("Today is not a valid Advent of Code day. Please specify a day");
@@ -102,69 +449,51 @@ fn parse_day(day: &str) -> usize {
   }
 }
 
-fn run_all_days() {
-  (1..=25).map(run_day).collect()
+fn parse_number<T: std::str::FromStr>(value: &str, name: &str) -> T {
+  match value.parse() {
+    Ok(i) => i,
+    Err(_) => panic!("{} is not a valid {}. Please provide a number.", value, name),
+  }
+}
+
+fn run_all_days() -> Vec<(usize, Timings)> {
+  run_days(&(1..=25).collect::<Vec<usize>>())
 }
+
+// Runs the given days, and returns the timings of those that succeeded
+fn run_days(days: &[usize]) -> Vec<(usize, Timings)> {
+  days
+    .iter()
+    .filter_map(|day| run_day(*day).map(|t| (*day, t)))
+    .collect()
+}
+
 // Panics if you provide a value outside the range of 1 to 25
-fn run_day(day: usize) {
+fn run_day(day: usize) -> Option<Timings> {
   println!("======== DAY {} ========", day);
-  // I'd like to do this with a macro, but I'm not sure how to do it.
   let input_fp = &format!("inputs/day{:02}.txt", day);
-  match day {
-    1 => day01::Day01::run_day(input_fp),
-    2 => day02::Day02::run_day(input_fp),
-    3 => day03::Day03::run_day(input_fp),
-    4 => day04::Day04::run_day(input_fp),
-    5 => day05::Day05::run_day(input_fp),
-    6 => day06::Day06::run_day(input_fp),
-    7 => day07::Day07::run_day(input_fp),
-    8 => day08::Day08::run_day(input_fp),
-    9 => day09::Day09::run_day(input_fp),
-    10 => day10::Day10::run_day(input_fp),
-    11 => day11::Day11::run_day(input_fp),
-    12 => day12::Day12::run_day(input_fp),
-    13 => day13::Day13::run_day(input_fp),
-    14 => day14::Day14::run_day(input_fp),
-    15 => day15::Day15::run_day(input_fp),
-    16 => day16::Day16::run_day(input_fp),
-    17 => day17::Day17::run_day(input_fp),
-    18 => day18::Day18::run_day(input_fp),
-    19 => day19::Day19::run_day(input_fp),
-    20 => day20::Day20::run_day(input_fp),
-    21 => day21::Day21::run_day(input_fp),
-    22 => day22::Day22::run_day(input_fp),
-    23 => day23::Day23::run_day(input_fp),
-    24 => day24::Day24::run_day(input_fp),
-    25 => day25::Day25::run_day(input_fp),
-    d => panic!("Provided unsupported day {}", d),
-  }
+  with_day!(day, D => D::run_day(input_fp))
 }
 
-fn download_all_input() {
-  (1..=25).map(download_input).collect()
+// Panics if the day has no visualisation
+fn visualize_day(day: usize, output: viz::Output) -> Result<(), parser::MyErr> {
+  let input_fp = &format!("inputs/day{:02}.txt", day);
+  match day {
+    11 => viz::visualize::<day11::Day11>(input_fp, day, output),
+    13 => viz::visualize::<day13::Day13>(input_fp, day, output),
+    20 => viz::visualize::<day20::Day20>(input_fp, day, output),
+    25 => viz::visualize::<day25::Day25>(input_fp, day, output),
+    d => panic!("Day {} has no visualisation", d),
+  }
 }
 
-fn download_input(day: usize) {
-  // Read session cookie from .session file
-  let session = fs::read_to_string(".session").expect("Could not find .session file");
-  let url = format!("https://adventofcode.com/{}/day/{}/input", YEAR, day);
-  let client = reqwest::blocking::Client::new();
-  let response = client
-    .get(url)
-    .header("cookie", format!("session={};", session))
-    .send()
-    .unwrap();
-
-  if response.status().is_success() {
-    let mut text = response.text().unwrap();
-    // Remove trailing newline
-    text.pop();
-    let path = format!("inputs/day{:02}.txt", day);
-    fs::write(&path, text).unwrap();
-    println!("Successfully downloaded input to {}", &path);
-  } else {
-    panic!("Could not get input for day {}. Is your correct session cookie in your .session file?", day)
+// Refuses to replace an existing key, since the inputs encrypted with it could not be read anymore
+fn generate_key() -> Result<(), parser::MyErr> {
+  if input::load_key()?.is_some() {
+    println!("A key is already set, remove it first if you really want a new one");
+    return Ok(());
   }
+  std::fs::write(input::KEY_FILE, input::generate_key())?;
+  println!("Wrote a new key to {}, share it with your team privately", input::KEY_FILE);
+  Ok(())
 }
-
-
@@ -1,33 +1,350 @@
-use nom::character::complete::{newline, satisfy};
-use nom::error::{Error, ParseError};
-use nom::multi::{many1, separated_list1};
+use nom::bytes::complete::tag;
+use nom::character::complete::{char, digit1, newline, satisfy};
+use nom::combinator::{cond, map, map_opt, map_res, opt, recognize, value};
+use nom::error::{Error, ErrorKind, ParseError};
+use nom::multi::{many1, separated_list0, separated_list1};
+use nom::sequence::{pair, preceded, separated_pair};
 use nom::{AsChar, Err, IResult, InputIter, InputLength, Parser, Slice};
-use std::ops::RangeFrom;
+use std::fmt::{self, Display, Formatter};
+use std::ops::{Index, IndexMut, RangeFrom, RangeInclusive};
+use std::str::FromStr;
+
+// The integer types `int` can parse. Only signed types accept a minus sign.
+pub trait Integer: FromStr + Copy {
+  const SIGNED: bool;
+}
+
+macro_rules! impl_integer {
+  ($signed:expr; $($t:ty),*) => {
+    $(
+      impl Integer for $t {
+        const SIGNED: bool = $signed;
+      }
+    )*
+  };
+}
+
+impl_integer!(false; u8, u16, u32, u64, u128, usize);
+impl_integer!(true; i8, i16, i32, i64, i128, isize);
+
+// A decimal integer of any type. A number that does not fit the type fails with `MapRes`, like
+// every other helper here fails with the error of the nom parser that did not match.
+pub fn int<T: Integer>(input: &str) -> IResult<&str, T> {
+  map_res(recognize(pair(cond(T::SIGNED, opt(char('-'))), digit1)), str::parse)(input)
+}
+
+// Items on consecutive lines, the most common shape of an input
+pub fn lines<'a, O, F>(f: F) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<O>>
+where
+  F: Parser<&'a str, O, Error<&'a str>>,
+{
+  separated_list0(newline, f)
+}
+
+// The end of a line followed by an empty line, which separates the sections of an input
+pub fn blank_line(input: &str) -> IResult<&str, ()> {
+  value((), pair(newline, newline))(input)
+}
+
+// Sections separated by blank lines, like the boards of day 4 or the scanners of day 19
+pub fn blocks<'a, O, F>(f: F) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<O>>
+where
+  F: Parser<&'a str, O, Error<&'a str>>,
+{
+  separated_list0(blank_line, f)
+}
+
+// Comma-separated integers on one line, like "3,4,3,1,2"
+pub fn csv_ints<T: Integer>(input: &str) -> IResult<&str, Vec<T>> {
+  separated_list0(char(','), int)(input)
+}
+
+// A range written as "a..b", which includes b
+pub fn range_inclusive<T: Integer>(input: &str) -> IResult<&str, RangeInclusive<T>> {
+  map(separated_pair(int, tag(".."), int), |(start, end)| start..=end)(input)
+}
+
+// The value after a label, like the range in "x=20..30" or the value in "Player 1 starting
+// position: 4". The label includes its punctuation.
+pub fn labelled<'a, O, F>(label: &'static str, f: F) -> impl FnMut(&'a str) -> IResult<&'a str, O>
+where
+  F: Parser<&'a str, O, Error<&'a str>>,
+{
+  preceded(tag(label), f)
+}
+
+// A single character that stands for a value, like the tiles of a map. Fails with `OneOf` on any
+// other character.
+pub fn char_map<T: Clone>(map: &[(char, T)]) -> impl Fn(&str) -> IResult<&str, T> + '_ {
+  move |input: &str| {
+    let found = input
+      .chars()
+      .next()
+      .and_then(|c| map.iter().find(|(m, _)| *m == c));
+    match found {
+      Some((c, value)) => Ok((&input[c.len_utf8()..], value.clone())),
+      None => Err(Err::Error(Error::new(input, ErrorKind::OneOf))),
+    }
+  }
+}
 
 pub fn digit<T: From<u32>>(input: &str) -> IResult<&str, T> {
-  let (cont, c) = satisfy(|c| c.is_digit(10))(input)?;
+  let (cont, c) = satisfy(|c| c.is_ascii_digit())(input)?;
   let res = c.to_digit(10).unwrap();
   Ok((cont, T::from(res)))
 }
 
-pub fn digit_grid<T: From<u32>>(input: &str) -> IResult<&str, Vec<Vec<T>>> {
+pub fn digit_grid<T: From<u32>>(input: &str) -> IResult<&str, Grid<T>> {
   grid(digit)(input)
 }
 
-pub fn grid<I, O, E, F>(f: F) -> impl FnMut(I) -> IResult<I, Vec<Vec<O>>, E>
+// Lines of cells, which all need to have the same length
+pub fn grid<I, O, E, F>(f: F) -> impl FnMut(I) -> IResult<I, Grid<O>, E>
 where
   I: Slice<RangeFrom<usize>> + InputIter + InputLength + Clone,
   <I as InputIter>::Item: AsChar,
   F: Parser<I, O, E>,
   E: ParseError<I>,
 {
-  separated_list1(newline, many1(f))
+  map_opt(separated_list1(newline, many1(f)), Grid::from_rows)
+}
+
+pub type Pos = (usize, usize);
+
+const OFFSETS_4: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+const OFFSETS_8: [(isize, isize); 8] = [
+  (-1, -1),
+  (0, -1),
+  (1, -1),
+  (-1, 0),
+  (1, 0),
+  (-1, 1),
+  (0, 1),
+  (1, 1),
+];
+
+fn bounded_offset((width, height): Pos, (x, y): Pos, (dx, dy): (isize, isize)) -> Option<Pos> {
+  let x = x.checked_add_signed(dx).filter(|x| *x < width)?;
+  let y = y.checked_add_signed(dy).filter(|y| *y < height)?;
+  Some((x, y))
+}
+
+fn wrapping_offset((width, height): Pos, (x, y): Pos, (dx, dy): (isize, isize)) -> Pos {
+  let x = (x as isize + dx).rem_euclid(width as isize) as usize;
+  let y = (y as isize + dy).rem_euclid(height as isize) as usize;
+  (x, y)
+}
+
+// A rectangular grid, stored row by row and indexed by (x, y) with y going down
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+  width: usize,
+  height: usize,
+  cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+  // None if the rows are not all equally long
+  pub fn from_rows(rows: Vec<Vec<T>>) -> Option<Grid<T>> {
+    let width = rows.first().map_or(0, |r| r.len());
+    if rows.iter().any(|r| r.len() != width) {
+      return None;
+    }
+    let height = rows.len();
+    let cells = rows.into_iter().flatten().collect();
+    Some(Grid {
+      width,
+      height,
+      cells,
+    })
+  }
+
+  pub fn from_fn<F: FnMut(Pos) -> T>(width: usize, height: usize, mut f: F) -> Grid<T> {
+    let cells = (0..height)
+      .flat_map(|y| (0..width).map(move |x| (x, y)))
+      .map(&mut f)
+      .collect();
+    Grid {
+      width,
+      height,
+      cells,
+    }
+  }
+
+  pub fn width(&self) -> usize {
+    self.width
+  }
+
+  pub fn height(&self) -> usize {
+    self.height
+  }
+
+  pub fn len(&self) -> usize {
+    self.cells.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.cells.is_empty()
+  }
+
+  pub fn get(&self, (x, y): Pos) -> Option<&T> {
+    if x < self.width && y < self.height {
+      self.cells.get(y * self.width + x)
+    } else {
+      None
+    }
+  }
+
+  pub fn get_mut(&mut self, (x, y): Pos) -> Option<&mut T> {
+    if x < self.width && y < self.height {
+      self.cells.get_mut(y * self.width + x)
+    } else {
+      None
+    }
+  }
+
+  // The position moved by (dx, dy), if it is still inside the grid
+  pub fn offset(&self, pos: Pos, delta: (isize, isize)) -> Option<Pos> {
+    bounded_offset((self.width, self.height), pos, delta)
+  }
+
+  // The position moved by (dx, dy), wrapping around the edges as if the grid were a torus
+  pub fn wrapping_offset(&self, pos: Pos, delta: (isize, isize)) -> Pos {
+    wrapping_offset((self.width, self.height), pos, delta)
+  }
+
+  // The orthogonal neighbours inside the grid. The neighbour iterators do not borrow the grid, so
+  // it can be changed while going over them.
+  pub fn neighbors4(&self, pos: Pos) -> impl Iterator<Item = Pos> {
+    let size = (self.width, self.height);
+    OFFSETS_4.into_iter().filter_map(move |d| bounded_offset(size, pos, d))
+  }
+
+  // The orthogonal and diagonal neighbours inside the grid
+  pub fn neighbors8(&self, pos: Pos) -> impl Iterator<Item = Pos> {
+    let size = (self.width, self.height);
+    OFFSETS_8.into_iter().filter_map(move |d| bounded_offset(size, pos, d))
+  }
+
+  pub fn wrapping_neighbors4(&self, pos: Pos) -> impl Iterator<Item = Pos> {
+    let size = (self.width, self.height);
+    OFFSETS_4.into_iter().map(move |d| wrapping_offset(size, pos, d))
+  }
+
+  pub fn wrapping_neighbors8(&self, pos: Pos) -> impl Iterator<Item = Pos> {
+    let size = (self.width, self.height);
+    OFFSETS_8.into_iter().map(move |d| wrapping_offset(size, pos, d))
+  }
+
+  // All positions, row by row
+  pub fn positions(&self) -> impl Iterator<Item = Pos> {
+    let width = self.width;
+    (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &T> {
+    self.cells.iter()
+  }
+
+  pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+    self.cells.iter_mut()
+  }
+
+  pub fn row(&self, y: usize) -> &[T] {
+    &self.cells[y * self.width..(y + 1) * self.width]
+  }
+
+  pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+    (0..self.height).map(move |y| self.row(y))
+  }
+
+  pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+    self.cells.iter().skip(x).step_by(self.width.max(1))
+  }
+
+  pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+    (0..self.width).map(move |x| self.column(x))
+  }
+
+  pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> Grid<U> {
+    Grid {
+      width: self.width,
+      height: self.height,
+      cells: self.cells.iter().map(f).collect(),
+    }
+  }
+}
+
+impl<T: Clone> Grid<T> {
+  pub fn filled(width: usize, height: usize, value: T) -> Grid<T> {
+    Grid {
+      width,
+      height,
+      cells: vec![value; width * height],
+    }
+  }
+
+  // Swaps rows and columns
+  pub fn transpose(&self) -> Grid<T> {
+    Grid::from_fn(self.height, self.width, |(x, y)| self[(y, x)].clone())
+  }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+  type Output = T;
+
+  fn index(&self, (x, y): Pos) -> &T {
+    assert!(x < self.width && y < self.height, "({}, {}) is outside the grid", x, y);
+    &self.cells[y * self.width + x]
+  }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+  fn index_mut(&mut self, (x, y): Pos) -> &mut T {
+    assert!(x < self.width && y < self.height, "({}, {}) is outside the grid", x, y);
+    &mut self.cells[y * self.width + x]
+  }
+}
+
+// The inverse of `grid`, for cells that display as a single character
+impl<T: Display> Display for Grid<T> {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    for (y, row) in self.rows().enumerate() {
+      if y > 0 {
+        writeln!(f)?;
+      }
+      for cell in row {
+        write!(f, "{}", cell)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+// The inverse of `separated_list0`, for items that can be displayed
+pub fn print_list<T: Display>(list: &[T], separator: &str) -> String {
+  list
+    .iter()
+    .map(|x| x.to_string())
+    .collect::<Vec<String>>()
+    .join(separator)
+}
+
+// Prints rows of cells that display as a single character, one row per line
+pub fn print_grid<T: Display>(grid: &[Vec<T>]) -> String {
+  grid
+    .iter()
+    .map(|row| row.iter().map(|x| x.to_string()).collect::<String>())
+    .collect::<Vec<String>>()
+    .join("\n")
 }
 
 #[derive(Debug)]
 pub enum MyErr {
   FileError(std::io::Error),
   ParseError(Err<Error<String>>),
+  // A missing or wrong key, or an encrypted input that was tampered with
+  CryptoError(String),
 }
 
 impl From<Err<Error<&str>>> for MyErr {
@@ -53,3 +370,90 @@ fn conv_error(e: Error<&str>) -> Error<String> {
     code: e.code,
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn sorted(positions: impl Iterator<Item = Pos>) -> Vec<Pos> {
+    let mut res: Vec<Pos> = positions.collect();
+    res.sort_unstable();
+    res
+  }
+
+  #[test]
+  fn finds_neighbours_inside_the_grid() {
+    let grid = Grid::filled(3, 2, 0);
+    assert_eq!(sorted(grid.neighbors4((0, 0))), vec![(0, 1), (1, 0)]);
+    assert_eq!(sorted(grid.neighbors8((0, 0))), vec![(0, 1), (1, 0), (1, 1)]);
+    assert_eq!(sorted(grid.neighbors4((2, 1))), vec![(1, 1), (2, 0)]);
+    assert_eq!(sorted(grid.neighbors8((1, 1))), vec![(0, 0), (0, 1), (1, 0), (2, 0), (2, 1)]);
+    assert_eq!(grid.offset((2, 1), (1, 0)), None);
+    assert_eq!(grid.offset((2, 1), (-2, -1)), Some((0, 0)));
+  }
+
+  #[test]
+  fn wraps_around_the_edges() {
+    let grid = Grid::filled(3, 2, 0);
+    assert_eq!(grid.wrapping_offset((2, 1), (1, 1)), (0, 0));
+    assert_eq!(grid.wrapping_offset((0, 0), (-4, -3)), (2, 1));
+    assert_eq!(sorted(grid.wrapping_neighbors4((0, 0))), vec![(0, 1), (0, 1), (1, 0), (2, 0)]);
+    assert_eq!(grid.wrapping_neighbors8((0, 0)).count(), 8);
+  }
+
+  #[test]
+  fn transposes() {
+    let grid = Grid::from_rows(vec![vec![1, 2, 3], vec![4, 5, 6]]).unwrap();
+    let transposed = grid.transpose();
+    assert_eq!((transposed.width(), transposed.height()), (2, 3));
+    assert_eq!(transposed.to_string(), "14\n25\n36");
+    assert_eq!(grid.column(1).collect::<Vec<_>>(), vec![&2, &5]);
+    assert_eq!(transposed.transpose(), grid);
+  }
+
+  #[test]
+  fn handles_grids_without_columns() {
+    let grid: Grid<u8> = Grid::from_rows(vec![vec![], vec![]]).unwrap();
+    assert_eq!((grid.width(), grid.height(), grid.len()), (0, 2, 0));
+    assert_eq!(grid.rows().collect::<Vec<_>>(), vec![&[] as &[u8], &[]]);
+    assert_eq!(grid.column(0).count(), 0);
+    assert_eq!(grid.columns().count(), 0);
+    assert_eq!(grid.get((0, 0)), None);
+  }
+
+  #[test]
+  fn refuses_ragged_rows() {
+    assert_eq!(Grid::from_rows(vec![vec![1, 2], vec![3]]), None);
+    assert!(digit_grid::<u32>("12\n3").is_err());
+  }
+
+  #[test]
+  fn parses_integers_that_fit_the_type() {
+    assert_eq!(int::<u8>("255"), Ok(("", 255)));
+    assert!(int::<u8>("256").is_err());
+    assert!(int::<u8>("-1").is_err());
+    assert_eq!(int::<i32>("-5,"), Ok((",", -5)));
+    assert_eq!(csv_ints::<u8>("3,4,3"), Ok(("", vec![3, 4, 3])));
+    assert_eq!(range_inclusive::<i64>("-3..7"), Ok(("", -3..=7)));
+  }
+
+  #[test]
+  fn splits_blocks_on_a_single_blank_line() {
+    assert_eq!(blocks(lines(int::<u8>))("1\n2\n\n3"), Ok(("", vec![vec![1, 2], vec![3]])));
+    // A second empty line is not part of the separator, so parsing stops after an empty block
+    let res = blocks(lines(int::<u8>))("1\n\n\n2");
+    assert_eq!(res, Ok(("\n2", vec![vec![1], vec![]])));
+  }
+
+  #[test]
+  fn parses_labels_and_mapped_characters() {
+    assert_eq!(labelled("x=", int::<i32>)("x=-4"), Ok(("", -4)));
+    assert!(labelled("x=", int::<i32>)("y=4").is_err());
+    let tiles = [('#', true), ('.', false)];
+    assert_eq!(many1(char_map(&tiles))("#.#"), Ok(("", vec![true, false, true])));
+    assert_eq!(
+      char_map(&tiles)("x"),
+      Err(Err::Error(Error::new("x", ErrorKind::OneOf)))
+    );
+  }
+}
@@ -0,0 +1,104 @@
+use crate::days::{Timings, TITLES};
+use crate::parser::MyErr;
+use crate::{run_day, YEAR};
+use std::any::Any;
+use std::fs::{read_to_string, write};
+use std::panic::catch_unwind;
+use std::time::Duration;
+
+const README: &str = "README.md";
+const START_MARKER: &str = "<!-- results:start -->";
+const END_MARKER: &str = "<!-- results:end -->";
+
+enum Outcome {
+  Solved(Timings),
+  Failed,
+  Unimplemented,
+  Panicked,
+}
+
+use Outcome::*;
+
+// Whether a panic came from a day that is still a stub
+pub fn is_unimplemented(payload: &(dyn Any + Send)) -> bool {
+  let message = payload
+    .downcast_ref::<&str>()
+    .map(|s| s.to_string())
+    .or_else(|| payload.downcast_ref::<String>().cloned())
+    .unwrap_or_default();
+  // The messages of `unimplemented!()` and `todo!()` respectively
+  message.contains("not implemented") || message.contains("not yet implemented")
+}
+
+fn run_outcome(day: usize) -> Outcome {
+  match catch_unwind(|| run_day(day)) {
+    Ok(Some(timings)) => Solved(timings),
+    Ok(None) => Failed,
+    Err(payload) if is_unimplemented(&*payload) => Unimplemented,
+    Err(_) => Panicked,
+  }
+}
+
+fn format_duration(d: Duration) -> String {
+  format!("{:.3} ms", d.as_secs_f64() * 1000.0)
+}
+
+fn build_table(outcomes: &[(usize, Outcome)]) -> String {
+  let mut res = String::from("| Day | Title | Part 1 | Part 2 | Total |\n");
+  res.push_str("|----:|-------|-------:|-------:|------:|\n");
+
+  let mut total_1 = Duration::ZERO;
+  let mut total_2 = Duration::ZERO;
+  for (day, outcome) in outcomes {
+    let title = format!(
+      "[{}](https://adventofcode.com/{}/day/{})",
+      TITLES[day - 1],
+      YEAR,
+      day
+    );
+    let columns = match outcome {
+      Solved(t) => {
+        total_1 += t.part_1;
+        total_2 += t.part_2;
+        [t.part_1, t.part_2, t.part_1 + t.part_2].map(format_duration)
+      }
+      Failed => ["error", "-", "-"].map(String::from),
+      Unimplemented => ["unimplemented", "-", "-"].map(String::from),
+      Panicked => ["panicked", "-", "-"].map(String::from),
+    };
+    res.push_str(&format!(
+      "| {} | {} | {} | {} | {} |\n",
+      day, title, columns[0], columns[1], columns[2]
+    ));
+  }
+  res.push_str(&format!(
+    "| | **Total** | {} | {} | **{}** |\n",
+    format_duration(total_1),
+    format_duration(total_2),
+    format_duration(total_1 + total_2)
+  ));
+  res
+}
+
+// Replaces the marked section of the README, or appends one if there is none yet
+fn update_readme(readme: &str, table: &str) -> String {
+  let section = format!("{}\n{}{}", START_MARKER, table, END_MARKER);
+  match (readme.find(START_MARKER), readme.find(END_MARKER)) {
+    (Some(start), Some(end)) if start < end => format!(
+      "{}{}{}",
+      &readme[..start],
+      section,
+      &readme[end + END_MARKER.len()..]
+    ),
+    _ => format!("{}\n\n## Results\n\n{}\n", readme.trim_end(), section),
+  }
+}
+
+pub fn report() -> Result<(), MyErr> {
+  let outcomes: Vec<(usize, Outcome)> = (1..=25).map(|day| (day, run_outcome(day))).collect();
+  let table = build_table(&outcomes);
+  let readme = read_to_string(README)?;
+  write(README, update_readme(&readme, &table))?;
+  println!("Updated the results table in {}", README);
+  Ok(())
+}
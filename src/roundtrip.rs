@@ -0,0 +1,92 @@
+use crate::days::{Day, Generate, Print};
+use log::debug;
+use rand::Rng;
+use std::fmt::Debug;
+
+// Parses the text, and fails unless all of it was consumed
+fn parse_all<D: Day>(text: &str) -> Result<D::Input, String> {
+  match D::parse(text) {
+    Ok((rest, input)) if rest.trim().is_empty() => Ok(input),
+    Ok((rest, _)) => {
+      let line = text[..text.len() - rest.len()].matches('\n').count() + 1;
+      Err(format!("stopped parsing at line {}", line))
+    }
+    Err(e) => Err(format!("could not be parsed: {:?}", e)),
+  }
+}
+
+// Checks for a number of generated inputs that the parser consumes all of them, and that parsing
+// the printed input gives an equal input back. Returns a description of the first failure.
+pub fn check<D: Generate + Print, R: Rng>(rng: &mut R, cases: usize) -> Result<(), String>
+where
+  D::Input: PartialEq + Debug,
+{
+  for case in 0..cases {
+    let generated = D::generate(rng, 1);
+    let input = parse_all::<D>(&generated)
+      .map_err(|e| format!("generated input {}: {}", case, e))?;
+    let printed = D::print(&input);
+    let reparsed = parse_all::<D>(&printed).map_err(|e| {
+      debug!("Printed input:\n{}", printed);
+      format!("printed input {}: {}", case, e)
+    })?;
+    if reparsed != input {
+      debug!("Parsed input:\n{:?}\nReparsed input:\n{:?}", input, reparsed);
+      return Err(format!(
+        "printed input {}: parsing it gave a different input",
+        case
+      ));
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::check;
+  use crate::with_day;
+  use rand::SeedableRng;
+  use rand_chacha::ChaCha8Rng;
+
+  // A test per day that checks parsing a printed input gives it back, for inputs generated from a
+  // fixed seed so that failures reproduce
+  macro_rules! roundtrip_tests {
+    ($($name:ident: $day:expr,)*) => {
+      $(
+        #[test]
+        fn $name() {
+          let mut rng = ChaCha8Rng::seed_from_u64($day);
+          assert_eq!(with_day!($day, D => check::<D, _>(&mut rng, 10)), Ok(()));
+        }
+      )*
+    };
+  }
+
+  roundtrip_tests! {
+    day01: 1,
+    day02: 2,
+    day03: 3,
+    day04: 4,
+    day05: 5,
+    day06: 6,
+    day07: 7,
+    day08: 8,
+    day09: 9,
+    day10: 10,
+    day11: 11,
+    day12: 12,
+    day13: 13,
+    day14: 14,
+    day15: 15,
+    day16: 16,
+    day17: 17,
+    day18: 18,
+    day19: 19,
+    day20: 20,
+    day21: 21,
+    day22: 22,
+    day23: 23,
+    day24: 24,
+    day25: 25,
+  }
+}
@@ -0,0 +1,137 @@
+use crate::days::*;
+use crate::input::normalise;
+use crate::parser::MyErr;
+use crate::with_day;
+use crate::YEAR;
+use log::{info, warn};
+use serde_json::{json, Value};
+use std::io::Error;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+fn part_json(answer: &Result<String, NoSolution>, seconds: f64) -> Value {
+  match answer {
+    Ok(answer) => json!({ "answer": answer, "seconds": seconds }),
+    Err(e) => json!({ "error": e.to_string(), "seconds": seconds }),
+  }
+}
+
+// Parses the input the same way `run` does, and answers with both parts and their timings
+fn solve_json<D: Day>(day: usize, input_string: &str) -> (u16, Value) {
+  let (input_string, _) = normalise(input_string);
+  match D::parse(&input_string) {
+    Err(e) => (400, json!({ "error": format!("{:?}", MyErr::from(e)) })),
+    Ok((_, input)) => {
+      let answers = D::solve(&input);
+      (
+        200,
+        json!({
+          "year": YEAR,
+          "day": day,
+          "part_1": part_json(&answers.part_1, answers.timings.part_1.as_secs_f64()),
+          "part_2": part_json(&answers.part_2, answers.timings.part_2.as_secs_f64()),
+        }),
+      )
+    }
+  }
+}
+
+// Only `POST /<year>/<day>` is routed, for the year and days this repository solves
+fn route(method: &Method, url: &str, body: &str) -> (u16, Value) {
+  if *method != Method::Post {
+    return (405, json!({ "error": "only POST requests are supported" }));
+  }
+  let segments: Vec<&str> = url.trim_matches('/').split('/').collect();
+  let (year, day) = match segments[..] {
+    [year, day] => match (year.parse::<usize>(), day.parse::<usize>()) {
+      (Ok(year), Ok(day)) => (year, day),
+      _ => return (404, json!({ "error": format!("{} is not a /<year>/<day> path", url) })),
+    },
+    _ => return (404, json!({ "error": format!("{} is not a /<year>/<day> path", url) })),
+  };
+  if year != YEAR {
+    return (404, json!({ "error": format!("only {} is solved here", YEAR) }));
+  }
+  if !(1..=25).contains(&day) {
+    return (404, json!({ "error": format!("{} is not a valid day", day) }));
+  }
+  // A panicking solution should only fail its own request
+  match catch_unwind(AssertUnwindSafe(|| with_day!(day, D => solve_json::<D>(day, body)))) {
+    Ok(res) => res,
+    Err(_) => (500, json!({ "error": format!("day {} panicked", day) })),
+  }
+}
+
+fn handle(mut request: Request) {
+  let mut body = String::new();
+  let (status, value) = match request.as_reader().read_to_string(&mut body) {
+    Ok(_) => route(request.method(), request.url(), &body),
+    Err(_) => (400, json!({ "error": "the input is not valid UTF-8" })),
+  };
+  info!("{} {} -> {}", request.method(), request.url(), status);
+  let header = Header::from_bytes("Content-Type", "application/json").unwrap();
+  let response = Response::from_string(value.to_string())
+    .with_status_code(status)
+    .with_header(header);
+  if let Err(e) = request.respond(response) {
+    warn!("Could not respond: {:?}", e);
+  }
+}
+
+// Binds to the port on localhost, or to any free port if it is 0
+fn listen(port: u16) -> Result<Server, MyErr> {
+  Ok(Server::http(("127.0.0.1", port)).map_err(Error::other)?)
+}
+
+// Serves requests one at a time on localhost until the process is stopped
+pub fn serve(port: u16) -> Result<(), MyErr> {
+  let server = listen(port)?;
+  println!("Listening on http://{}", server.server_addr());
+  for request in server.incoming_requests() {
+    handle(request);
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{handle, listen};
+  use std::io::{Read, Write};
+  use std::net::TcpStream;
+  use std::thread;
+
+  // Answers a single request on a free port, and returns the raw response
+  fn post(path: &str, body: &str) -> String {
+    let server = listen(0).unwrap();
+    let addr = server.server_addr().to_ip().unwrap();
+    let handler = thread::spawn(move || handle(server.recv().unwrap()));
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(
+      stream,
+      "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+      path,
+      addr,
+      body.len(),
+      body
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    handler.join().unwrap();
+    response
+  }
+
+  #[test]
+  fn solves_a_posted_input() {
+    let response = post("/2021/1", "199\n200\n208\n210\n200\n207\n240\n269\n260\n263\n");
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    assert!(response.contains(r#""part_1":{"answer":"7""#), "{}", response);
+    assert!(response.contains(r#""part_2":{"answer":"5""#), "{}", response);
+  }
+
+  #[test]
+  fn refuses_other_years() {
+    let response = post("/2020/1", "1\n");
+    assert!(response.starts_with("HTTP/1.1 404"), "{}", response);
+  }
+}
@@ -0,0 +1,155 @@
+use crate::days::*;
+use crate::download::{input_path, Downloader};
+use crate::input;
+use crate::parser::MyErr;
+use crate::report::is_unimplemented;
+use crate::with_day;
+use nom::bytes::complete::is_not;
+use nom::character::complete::{char, newline, u8};
+use nom::multi::separated_list0;
+use nom::sequence::tuple;
+use nom::IResult;
+use std::collections::HashMap;
+use std::fs::{read_to_string, write};
+use std::io::ErrorKind;
+use std::panic::catch_unwind;
+
+// Tab-separated: day, part, answer. Newlines in answers are written as "\n".
+const ANSWERS_FILE: &str = ".answers";
+
+type Answers = HashMap<(u8, u8), String>;
+
+fn parse_answer(input: &str) -> IResult<&str, ((u8, u8), String)> {
+  let (cont, (day, _, part, _, answer)) = tuple((u8, char('\t'), u8, char('\t'), is_not("\n")))(input)?;
+  Ok((cont, ((day, part), answer.replace("\\n", "\n"))))
+}
+
+fn load_answers() -> Result<Answers, MyErr> {
+  let input_string = match read_to_string(ANSWERS_FILE) {
+    Ok(s) => s,
+    Err(e) if e.kind() == ErrorKind::NotFound => return Ok(HashMap::new()),
+    Err(e) => return Err(e.into()),
+  };
+  let (_, answers) = separated_list0(newline, parse_answer)(&input_string)?;
+  Ok(answers.into_iter().collect())
+}
+
+fn save_answers(answers: &Answers) -> Result<(), MyErr> {
+  let mut keys: Vec<&(u8, u8)> = answers.keys().collect();
+  keys.sort();
+  let lines: Vec<String> = keys
+    .into_iter()
+    .map(|(day, part)| format!("{}\t{}\t{}", day, part, answers[&(*day, *part)].replace('\n', "\\n")))
+    .collect();
+  write(ANSWERS_FILE, lines.join("\n") + "\n")?;
+  Ok(())
+}
+
+enum DayStatus {
+  Stub,
+  NoInput,
+  // Could not parse the input, or panicked
+  Broken,
+  Ran([Result<String, NoSolution>; 2]),
+}
+
+use DayStatus::*;
+
+fn day_status(day: usize) -> DayStatus {
+  let input_fp = input_path(day);
+  if !input::exists(&input_fp) {
+    // Without an input only a stub parser can be found, by giving it an empty one
+    return match catch_unwind(|| with_day!(day, D => D::parse("").is_ok())) {
+      Err(payload) if is_unimplemented(&*payload) => Stub,
+      _ => NoInput,
+    };
+  }
+  match catch_unwind(|| with_day!(day, D => D::parse_file(&input_fp).map(|input| D::solve(&input)))) {
+    Ok(Ok(answers)) => Ran([answers.part_1, answers.part_2]),
+    Ok(Err(_)) => Broken,
+    Err(payload) if is_unimplemented(&*payload) => Stub,
+    Err(_) => Broken,
+  }
+}
+
+// Records the current answers of a day as the ones the site accepted
+pub fn accept(day: usize, part: Option<u8>) -> Result<(), MyErr> {
+  let mut answers = load_answers()?;
+  let results = match day_status(day) {
+    Ran(results) => results,
+    _ => {
+      println!("Day {} does not run, so there is nothing to accept", day);
+      return Ok(());
+    }
+  };
+  for (p, result) in (1..=2).zip(results) {
+    if part.is_some_and(|part| part != p) {
+      continue;
+    }
+    match result {
+      Ok(answer) => {
+        println!("Accepted part {}: {}", p, answer);
+        answers.insert((day as u8, p), answer);
+      }
+      Err(e) => println!("Part {} has no answer to accept: {}", p, e),
+    }
+  }
+  save_answers(&answers)
+}
+
+fn part_symbol(day: usize, part: u8, result: &Result<String, NoSolution>, answers: &Answers) -> char {
+  match (result, answers.get(&(day as u8, part))) {
+    (Err(_), _) => '-',
+    (Ok(_), None) => '?',
+    (Ok(answer), Some(accepted)) if answer == accepted => '✓',
+    (Ok(_), Some(_)) => '✗',
+  }
+}
+
+fn cell(day: usize, status: &DayStatus, answers: &Answers, stars: Option<usize>) -> String {
+  let state = match status {
+    Stub => String::from("stub"),
+    NoInput => String::from("no input"),
+    Broken => String::from("broken"),
+    Ran(results) => format!(
+      "{} {}",
+      part_symbol(day, 1, &results[0], answers),
+      part_symbol(day, 2, &results[1], answers)
+    ),
+  };
+  match stars {
+    Some(n) => format!("{:>2} {:<8} {:<2}", day, state, "*".repeat(n)),
+    None => format!("{:>2} {:<8}", day, state),
+  }
+}
+
+// Prints the days as a calendar, five days per row
+pub fn status(with_stars: bool) -> Result<(), MyErr> {
+  let answers = load_answers()?;
+  let stars = if with_stars {
+    match Downloader::new(false).and_then(|mut d| d.stars()) {
+      Ok(stars) => Some(stars),
+      Err(e) => {
+        println!("Could not get the stars: {}", e);
+        None
+      }
+    }
+  } else {
+    None
+  };
+  let cells: Vec<String> = (1..=25)
+    .map(|day| {
+      let status = day_status(day);
+      cell(day, &status, &answers, stars.as_ref().map(|s| s[day - 1]))
+    })
+    .collect();
+  for row in cells.chunks(5) {
+    println!("{}", row.join(" │ ").trim_end());
+  }
+  println!();
+  println!("✓ matches the accepted answer, ✗ differs from it, ? has no accepted answer yet, - has no answer");
+  if let Some(stars) = stars {
+    println!("{} stars collected", stars.iter().sum::<usize>());
+  }
+  Ok(())
+}
@@ -0,0 +1,98 @@
+use crate::days::{Colour, Frame, Visualize};
+use crate::parser::MyErr;
+use std::fs::{create_dir_all, write};
+use std::path::Path;
+use std::thread::sleep;
+use std::time::Duration;
+
+pub enum Output<'a> {
+  // Play the frames in the terminal, waiting the given duration between them
+  Terminal(Duration),
+  // Write every frame to a numbered image file in the given directory, with every cell becoming a
+  // square of the given number of pixels
+  Images(&'a Path, usize),
+}
+
+pub fn visualize<V: Visualize>(fp: &str, day: usize, output: Output) -> Result<(), MyErr> {
+  let input = V::parse_file(fp)?;
+  let frames = V::frames(&input);
+  match output {
+    Output::Terminal(delay) => play(frames, delay),
+    Output::Images(dir, scale) => export(frames, dir, day, scale)?,
+  }
+  Ok(())
+}
+
+fn play<I: Iterator<Item = Frame>>(frames: I, delay: Duration) {
+  for frame in frames {
+    let mut res = String::from("\x1b[2J\x1b[H");
+    for row in frame {
+      for (c, colour) in row {
+        match colour {
+          Some((r, g, b)) => res.push_str(&format!("\x1b[38;2;{};{};{}m{}\x1b[0m", r, g, b, c)),
+          None => res.push(c),
+        }
+      }
+      res.push('\n');
+    }
+    print!("{}", res);
+    sleep(delay);
+  }
+}
+
+// Whether a cell without a colour should be drawn as a filled pixel
+fn is_filled(c: char) -> bool {
+  !matches!(c, ' ' | '.' | '░')
+}
+
+fn cell_colour((c, colour): &(char, Option<Colour>)) -> Colour {
+  colour.unwrap_or(if is_filled(*c) { (255, 255, 255) } else { (0, 0, 0) })
+}
+
+// Frames with colours become PPM images, others become black and white PBM images
+fn to_image(frame: &Frame, scale: usize) -> (&'static str, Vec<u8>) {
+  let height = frame.len() * scale;
+  let width = frame.first().map_or(0, |r| r.len()) * scale;
+  let has_colours = frame.iter().flatten().any(|(_, colour)| colour.is_some());
+  let rows = frame.iter().flat_map(|row| std::iter::repeat_n(row, scale));
+  if has_colours {
+    let mut res = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    for row in rows {
+      for cell in row {
+        let (r, g, b) = cell_colour(cell);
+        for _ in 0..scale {
+          res.extend([r, g, b]);
+        }
+      }
+    }
+    ("ppm", res)
+  } else {
+    let mut res = format!("P1\n{} {}\n", width, height).into_bytes();
+    for row in rows {
+      let line: Vec<&str> = row
+        .iter()
+        .flat_map(|(c, _)| std::iter::repeat_n(if is_filled(*c) { "1" } else { "0" }, scale))
+        .collect();
+      res.extend(line.join(" ").into_bytes());
+      res.push(b'\n');
+    }
+    ("pbm", res)
+  }
+}
+
+fn export<I: Iterator<Item = Frame>>(
+  frames: I,
+  dir: &Path,
+  day: usize,
+  scale: usize,
+) -> Result<(), MyErr> {
+  create_dir_all(dir)?;
+  let mut count = 0;
+  for (i, frame) in frames.enumerate() {
+    let (extension, image) = to_image(&frame, scale);
+    write(dir.join(format!("day{:02}_{:04}.{}", day, i, extension)), image)?;
+    count += 1;
+  }
+  println!("Wrote {} frames to {}", count, dir.display());
+  Ok(())
+}